@@ -0,0 +1,115 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+// Byte offsets of the fields patched once the length is known
+const FORM_SIZE_OFFSET: u64 = 4;
+const COMM_FRAMES_OFFSET: u64 = 22;
+const SSND_SIZE_OFFSET: u64 = 42;
+const HEADER_LEN: u32 = 54;
+
+// Bit depth an AIFF export should use for a source of the given depth
+pub fn bits_for(bits_per_sample: u16) -> u16 {
+    if bits_per_sample <= 16 {
+        16
+    } else {
+        24
+    }
+}
+
+// 80-bit IEEE 754 extended float, the encoding COMM uses for the sample rate
+fn extended_from_u32(value: u32) -> [u8; 10] {
+    let mut bytes = [0u8; 10];
+    if value == 0 {
+        return bytes;
+    }
+    let exponent = 31 - value.leading_zeros();
+    let mantissa = (value as u64) << (63 - exponent);
+    bytes[..2].copy_from_slice(&((exponent + 16383) as u16).to_be_bytes());
+    bytes[2..].copy_from_slice(&mantissa.to_be_bytes());
+    bytes
+}
+
+// Streaming writer for uncompressed big-endian AIFF (COMM + SSND chunks),
+// since hound only writes WAV
+pub struct AiffWriter {
+    writer: BufWriter<File>,
+    channels: u16,
+    bits_per_sample: u16,
+    samples_written: u64,
+}
+
+impl AiffWriter {
+    pub fn create(path: &Path, channels: u16, sample_rate: u32, bits_per_sample: u16) -> Result<Self, String> {
+        if bits_per_sample != 16 && bits_per_sample != 24 {
+            return Err(format!("AIFF output supports 16 or 24 bits, got {}", bits_per_sample));
+        }
+        if channels == 0 {
+            return Err("AIFF output needs at least one channel".to_string());
+        }
+        let file = File::create(path).map_err(|e| format!("Failed to create AIFF file: {}", e))?;
+        let mut writer = BufWriter::new(file);
+
+        let mut header = Vec::with_capacity(HEADER_LEN as usize);
+        header.extend_from_slice(b"FORM");
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(b"AIFF");
+        header.extend_from_slice(b"COMM");
+        header.extend_from_slice(&18u32.to_be_bytes());
+        header.extend_from_slice(&(channels as i16).to_be_bytes());
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(&(bits_per_sample as i16).to_be_bytes());
+        header.extend_from_slice(&extended_from_u32(sample_rate));
+        header.extend_from_slice(b"SSND");
+        header.extend_from_slice(&8u32.to_be_bytes());
+        // Offset and block size, both unused
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(&0u32.to_be_bytes());
+        writer.write_all(&header).map_err(|e| format!("Failed to write AIFF header: {}", e))?;
+
+        Ok(Self {
+            writer,
+            channels,
+            bits_per_sample,
+            samples_written: 0,
+        })
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        for &sample in samples {
+            let sample = sample.clamp(-1.0, 1.0);
+            let result = if self.bits_per_sample == 16 {
+                let value = (sample * 32768.0).clamp(-32768.0, 32767.0) as i16;
+                self.writer.write_all(&value.to_be_bytes())
+            } else {
+                let value = (sample * 8_388_608.0).clamp(-8_388_608.0, 8_388_607.0) as i32;
+                self.writer.write_all(&value.to_be_bytes()[1..])
+            };
+            result.map_err(|e| format!("Failed to write AIFF sample: {}", e))?;
+        }
+        self.samples_written += samples.len() as u64;
+        Ok(())
+    }
+
+    pub fn finalize(mut self) -> Result<(), String> {
+        let io_error = |e: std::io::Error| format!("Failed to finalize AIFF file: {}", e);
+        let data_len = self.samples_written * (self.bits_per_sample / 8) as u64;
+        // Chunks are padded to an even length; the pad isn't counted in SSND
+        let padded_len = data_len + data_len % 2;
+        if data_len % 2 == 1 {
+            self.writer.write_all(&[0]).map_err(io_error)?;
+        }
+        let form_size = u32::try_from(HEADER_LEN as u64 - 8 + padded_len)
+            .map_err(|_| "Recording is too long for an AIFF file".to_string())?;
+        let frames = (self.samples_written / self.channels as u64) as u32;
+
+        let mut file = self.writer.into_inner().map_err(|e| io_error(e.into_error()))?;
+        file.seek(SeekFrom::Start(FORM_SIZE_OFFSET)).map_err(io_error)?;
+        file.write_all(&form_size.to_be_bytes()).map_err(io_error)?;
+        file.seek(SeekFrom::Start(COMM_FRAMES_OFFSET)).map_err(io_error)?;
+        file.write_all(&frames.to_be_bytes()).map_err(io_error)?;
+        file.seek(SeekFrom::Start(SSND_SIZE_OFFSET)).map_err(io_error)?;
+        file.write_all(&((data_len + 8) as u32).to_be_bytes()).map_err(io_error)?;
+        file.sync_all().map_err(io_error)
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::Serialize;
+use tauri::State;
+
+use crate::capture;
+use crate::RecordingState;
+
+// Buffer sizes tried, smallest (lowest latency) first
+const CANDIDATE_FRAMES: [u32; 7] = [64, 128, 256, 512, 1024, 2048, 4096];
+const DEFAULT_TRIAL_MS: u64 = 2_000;
+const MAX_TRIAL_MS: u64 = 10_000;
+// Callbacks in the first moments after start are often uneven on any size
+const SETTLE_MS: u64 = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferTrial {
+    pub buffer_frames: u32,
+    pub latency_ms: f64,
+    pub callbacks: u64,
+    // Gaps between buffers longer than the audio they carried
+    pub dropouts: u32,
+    pub stream_errors: u32,
+    // Set when the device refused this size
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferCalibration {
+    pub device: String,
+    pub sample_rate: u32,
+    pub trials: Vec<BufferTrial>,
+    // Smallest size that ran without a dropout or stream error
+    pub recommended_frames: Option<u32>,
+}
+
+#[derive(Default)]
+struct TrialStats {
+    callbacks: u64,
+    dropouts: u32,
+    stream_errors: u32,
+    // When the next buffer should start if nothing was lost
+    expected: Option<cpal::StreamInstant>,
+    settle_until: Option<cpal::StreamInstant>,
+}
+
+// Record briefly at each buffer size the device allows, counting dropouts,
+// and recommend the smallest one that stayed clean
+#[tauri::command]
+pub async fn calibrate_buffer_size(
+    state: State<'_, RecordingState>,
+    device_name: Option<String>,
+    trial_ms: Option<u64>,
+) -> Result<BufferCalibration, String> {
+    let trial_ms = trial_ms.unwrap_or(DEFAULT_TRIAL_MS);
+    if !(SETTLE_MS + 1..=MAX_TRIAL_MS).contains(&trial_ms) {
+        return Err(format!("Trial length must be between {} and {} ms", SETTLE_MS + 1, MAX_TRIAL_MS));
+    }
+    if *state.is_recording.lock().map_err(|e| e.to_string())? {
+        return Err("Cannot calibrate while recording".to_string());
+    }
+    // Trials would be measuring the device alongside the kept stream
+    if state.kept_input.lock().map_err(|e| e.to_string())?.as_ref().is_some_and(|input| input.is_open()) {
+        return Err("Release the kept input stream before calibrating".to_string());
+    }
+    // Every size gets a trial of its own, which adds up to over a minute
+    tauri::async_runtime::spawn_blocking(move || calibrate(device_name, trial_ms))
+        .await
+        .map_err(|e| format!("Failed to calibrate buffer size: {}", e))?
+}
+
+fn calibrate(device_name: Option<String>, trial_ms: u64) -> Result<BufferCalibration, String> {
+    let device = capture::find_input_device(device_name.as_deref())?;
+    let name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get default input config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let sizes: Vec<u32> = match *supported.buffer_size() {
+        cpal::SupportedBufferSize::Range { min, max } => {
+            CANDIDATE_FRAMES.iter().copied().filter(|frames| (min..=max).contains(frames)).collect()
+        }
+        cpal::SupportedBufferSize::Unknown => CANDIDATE_FRAMES.to_vec(),
+    };
+    if sizes.is_empty() {
+        return Err("Device supports none of the buffer sizes to try".to_string());
+    }
+    let base: cpal::StreamConfig = supported.into();
+
+    let mut trials = Vec::new();
+    for buffer_frames in sizes {
+        let config = cpal::StreamConfig {
+            buffer_size: cpal::BufferSize::Fixed(buffer_frames),
+            ..base.clone()
+        };
+        let mut trial = BufferTrial {
+            buffer_frames,
+            latency_ms: buffer_frames as f64 * 1000.0 / config.sample_rate.0 as f64,
+            callbacks: 0,
+            dropouts: 0,
+            stream_errors: 0,
+            error: None,
+        };
+        match run_trial(&device, &config, sample_format, Duration::from_millis(trial_ms)) {
+            Ok(stats) => {
+                trial.callbacks = stats.callbacks;
+                trial.dropouts = stats.dropouts;
+                trial.stream_errors = stats.stream_errors;
+            }
+            Err(e) => trial.error = Some(e),
+        }
+        trials.push(trial);
+    }
+
+    let recommended_frames = trials
+        .iter()
+        .find(|trial| trial.error.is_none() && trial.callbacks > 0 && trial.dropouts == 0 && trial.stream_errors == 0)
+        .map(|trial| trial.buffer_frames);
+    Ok(BufferCalibration {
+        device: name,
+        sample_rate: base.sample_rate.0,
+        trials,
+        recommended_frames,
+    })
+}
+
+fn run_trial(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+    duration: Duration,
+) -> Result<TrialStats, String> {
+    let rate = config.sample_rate.0 as f64;
+    let channels = config.channels as usize;
+    let stats = Arc::new(Mutex::new(TrialStats::default()));
+    let data_stats = stats.clone();
+    let error_stats = stats.clone();
+    let stream = capture::build_timed_input_stream(
+        device,
+        config,
+        sample_format,
+        move |data, captured| {
+            let Ok(mut stats) = data_stats.lock() else {
+                return;
+            };
+            let settle_until = *stats
+                .settle_until
+                .get_or_insert_with(|| captured.add(Duration::from_millis(SETTLE_MS)).unwrap_or(captured));
+            let length = Duration::from_secs_f64((data.len() / channels.max(1)) as f64 / rate);
+            // Allow half a buffer of timestamp jitter before calling it a gap
+            if let Some(late) = stats.expected.and_then(|expected| captured.duration_since(&expected)) {
+                if late > length / 2 && captured.duration_since(&settle_until).is_some() {
+                    stats.dropouts += 1;
+                }
+            }
+            stats.expected = captured.add(length);
+            stats.callbacks += 1;
+        },
+        move |_| {
+            if let Ok(mut stats) = error_stats.lock() {
+                stats.stream_errors += 1;
+            }
+        },
+    )?;
+    stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+    thread::sleep(duration);
+    drop(stream);
+
+    let mut stats = stats.lock().map_err(|e| e.to_string())?;
+    Ok(std::mem::take(&mut *stats))
+}
@@ -0,0 +1,208 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{FromSample, SizedSample};
+use serde::{Deserialize, Serialize};
+
+// Which default input to use. Windows keeps separate defaults for calls
+// (Communications) and for everything else; other platforms have just one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeviceRole {
+    #[default]
+    Console,
+    Communications,
+    Multimedia,
+}
+
+// Find an input device by name, or the host default when no name is given
+pub fn find_input_device(name: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match name {
+        Some(name) => host
+            .input_devices()
+            .map_err(|e| format!("Failed to enumerate input devices: {}", e))?
+            .find(|device| device.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| format!("Input device not found: {}", name)),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| "No input device available".to_string()),
+    }
+}
+
+// The default input for `role`, falling back to the host default where the
+// platform has no per-role defaults or the endpoint isn't visible to cpal
+pub fn find_default_input_device(role: DeviceRole) -> Result<cpal::Device, String> {
+    if role != DeviceRole::Console {
+        if let Some(name) = role::default_capture_name(role) {
+            if let Ok(device) = find_input_device(Some(&name)) {
+                return Ok(device);
+            }
+        }
+    }
+    find_input_device(None)
+}
+
+#[cfg(not(windows))]
+mod role {
+    use super::DeviceRole;
+
+    pub fn default_capture_name(_role: DeviceRole) -> Option<String> {
+        None
+    }
+}
+
+// cpal names WASAPI devices by their friendly name, so the role's default
+// endpoint is looked up and matched to a cpal device by that name
+#[cfg(windows)]
+mod role {
+    use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
+    use windows::Win32::Media::Audio::{eCapture, eCommunications, eConsole, eMultimedia, IMMDeviceEnumerator, MMDeviceEnumerator};
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoTaskMemFree, CoUninitialize, CLSCTX_ALL, COINIT_APARTMENTTHREADED, STGM_READ,
+    };
+    use windows::Win32::System::Com::StructuredStorage::PropVariantToStringAlloc;
+    use super::DeviceRole;
+
+    pub fn default_capture_name(role: DeviceRole) -> Option<String> {
+        let role = match role {
+            DeviceRole::Console => eConsole,
+            DeviceRole::Communications => eCommunications,
+            DeviceRole::Multimedia => eMultimedia,
+        };
+        unsafe {
+            // Apartment-threaded like cpal; only undone if this call did it
+            let initialized = CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok();
+            let name = (|| {
+                let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL).ok()?;
+                let device = enumerator.GetDefaultAudioEndpoint(eCapture, role).ok()?;
+                let store = device.OpenPropertyStore(STGM_READ).ok()?;
+                let value = store.GetValue(&PKEY_Device_FriendlyName).ok()?;
+                let text = PropVariantToStringAlloc(&value).ok()?;
+                let name = text.to_string().ok();
+                CoTaskMemFree(Some(text.as_ptr() as *const _));
+                name
+            })();
+            if initialized {
+                CoUninitialize();
+            }
+            name
+        }
+    }
+}
+
+// Input config at `sample_rate`, or the device default when none is asked for.
+// With `nearest` set, an unsupported rate falls back to the highest supported
+// rate below it; the returned rate is the one the device will actually run at.
+pub fn select_input_config(
+    device: &cpal::Device,
+    sample_rate: Option<u32>,
+    nearest: bool,
+) -> Result<cpal::SupportedStreamConfig, String> {
+    let default = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get default input config: {}", e))?;
+    let Some(requested) = sample_rate else {
+        return Ok(default);
+    };
+    if default.sample_rate().0 == requested {
+        return Ok(default);
+    }
+
+    let ranges: Vec<cpal::SupportedStreamConfigRange> = device
+        .supported_input_configs()
+        .map_err(|e| format!("Failed to get supported input configs: {}", e))?
+        .collect();
+    // Prefer ranges matching the default layout so only the rate changes
+    let rank = |range: &cpal::SupportedStreamConfigRange| {
+        (range.channels() == default.channels(), range.sample_format() == default.sample_format())
+    };
+
+    let exact = ranges
+        .iter()
+        .filter(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&requested))
+        .max_by_key(|range| rank(range));
+    if let Some(range) = exact {
+        return Ok((*range).with_sample_rate(cpal::SampleRate(requested)));
+    }
+    if !nearest {
+        return Err(format!("Input device does not support {} Hz", requested));
+    }
+
+    ranges
+        .iter()
+        .filter(|range| range.min_sample_rate().0 <= requested)
+        .max_by_key(|range| (range.max_sample_rate().0.min(requested), rank(range)))
+        .map(|range| {
+            let rate = range.max_sample_rate().0.min(requested);
+            (*range).with_sample_rate(cpal::SampleRate(rate))
+        })
+        .ok_or_else(|| format!("Input device supports no sample rate at or below {} Hz", requested))
+}
+
+// Build an input stream that hands every buffer to `on_data` as interleaved f32
+// samples, whatever the device's native sample format is
+pub fn build_f32_input_stream<F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+    mut on_data: F,
+) -> Result<cpal::Stream, String>
+where
+    F: FnMut(&[f32]) + Send + 'static,
+{
+    build_timed_input_stream(
+        device,
+        config,
+        sample_format,
+        move |data, _| on_data(data),
+        |err| eprintln!("Stream error: {}", err),
+    )
+}
+
+// Like build_f32_input_stream, but also passes when each buffer was captured
+// and hands stream errors to `on_error` instead of logging them
+pub fn build_timed_input_stream<F, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+    on_data: F,
+    on_error: E,
+) -> Result<cpal::Stream, String>
+where
+    F: FnMut(&[f32], cpal::StreamInstant) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    match sample_format {
+        cpal::SampleFormat::F32 => build_stream::<f32, F, E>(device, config, on_data, on_error),
+        cpal::SampleFormat::I16 => build_stream::<i16, F, E>(device, config, on_data, on_error),
+        cpal::SampleFormat::U16 => build_stream::<u16, F, E>(device, config, on_data, on_error),
+        _ => Err("Unsupported sample format".to_string()),
+    }
+}
+
+fn build_stream<T, F, E>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut on_data: F,
+    on_error: E,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+    F: FnMut(&[f32], cpal::StreamInstant) + Send + 'static,
+    E: FnMut(cpal::StreamError) + Send + 'static,
+{
+    // Reused across callbacks so the audio thread doesn't allocate per buffer
+    let mut buffer: Vec<f32> = Vec::new();
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], info: &cpal::InputCallbackInfo| {
+                buffer.clear();
+                buffer.extend(data.iter().map(|&sample| sample.to_sample::<f32>()));
+                on_data(&buffer, info.timestamp().capture);
+            },
+            on_error,
+            None,
+        )
+        .map_err(|e| format!("Failed to build input stream: {}", e))
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+use serde::Serialize;
+
+use crate::decode;
+use crate::levels::{self, ChannelLevels};
+
+// An unplugged input usually reads as digital silence or a faint hiss well
+// under anything a connected mic picks up
+const DEAD_BELOW_DBFS: f32 = -80.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelActivity {
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    pub dead: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadChannelReport {
+    pub threshold_dbfs: f32,
+    // In channel order
+    pub channels: Vec<ChannelActivity>,
+    // Zero-based indices of the dead channels
+    pub dead: Vec<u16>,
+}
+
+// Find channels whose level stays below `threshold_dbfs` RMS for the whole
+// recording, e.g. a mic that was never plugged in
+#[tauri::command]
+pub async fn detect_dead_channels(path: String, threshold_dbfs: Option<f32>) -> Result<DeadChannelReport, String> {
+    let threshold_dbfs = threshold_dbfs.unwrap_or(DEAD_BELOW_DBFS);
+    if !(levels::MIN_DBFS..=0.0).contains(&threshold_dbfs) {
+        return Err(format!("Threshold must be between {} and 0 dBFS", levels::MIN_DBFS));
+    }
+    let mut source = decode::open(Path::new(&path))?;
+    let mut levels = ChannelLevels::new(source.info().channels as usize);
+    let mut buffer = Vec::new();
+    while source.read_chunk(&mut buffer)? > 0 {
+        levels.push_interleaved(&buffer);
+    }
+    if levels.frames() == 0 {
+        return Err("Recording has no audio".to_string());
+    }
+
+    let channels: Vec<ChannelActivity> = levels
+        .peak_dbfs()
+        .into_iter()
+        .zip(levels.rms_dbfs())
+        .map(|(peak_dbfs, rms_dbfs)| ChannelActivity {
+            peak_dbfs,
+            rms_dbfs,
+            dead: rms_dbfs < threshold_dbfs,
+        })
+        .collect();
+    let dead = channels
+        .iter()
+        .enumerate()
+        .filter(|(_, channel)| channel.dead)
+        .map(|(index, _)| index as u16)
+        .collect();
+    Ok(DeadChannelReport {
+        threshold_dbfs,
+        channels,
+        dead,
+    })
+}
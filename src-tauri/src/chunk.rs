@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use crate::metadata;
+use crate::recorder;
+use crate::wav::{WavSink, WavStream};
+
+const CHUNK_FRAMES: usize = 4096;
+
+// Split a finished WAV into `<stem>-001.wav`, `<stem>-002.wav`, … of
+// `chunk_secs` each in `out_dir`, the last holding whatever is left. Returns
+// the chunk paths in order.
+#[tauri::command]
+pub async fn chunk_recording(input: String, chunk_secs: f64, out_dir: String) -> Result<Vec<String>, String> {
+    if !(chunk_secs > 0.0 && chunk_secs.is_finite()) {
+        return Err("Chunk length must be greater than 0 seconds".to_string());
+    }
+    let input = Path::new(&input);
+    let out_dir = Path::new(&out_dir);
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let mut stream = WavStream::open(input)?;
+    let spec = stream.spec();
+    let channels = spec.channels as usize;
+    let chunk_frames = ((chunk_secs * spec.sample_rate as f64).round() as u64).max(1);
+    let stem = input.file_stem().ok_or("Input has no file name")?;
+    let base = out_dir.join(stem);
+    // The chunks keep the input's bytes, pre-emphasis and all, so each gets
+    // the input's metadata with its own start time
+    let metadata = metadata::sidecar_path(input)
+        .exists()
+        .then(|| metadata::read(input))
+        .transpose()?;
+
+    let mut paths = Vec::new();
+    let mut sink: Option<WavSink> = None;
+    let mut left_in_chunk = 0u64;
+    let mut buffer = Vec::new();
+    while stream.read_chunk(CHUNK_FRAMES, &mut buffer)? > 0 {
+        let mut data = &buffer[..];
+        while !data.is_empty() {
+            if left_in_chunk == 0 {
+                if let Some(finished) = sink.take() {
+                    finished.finalize()?;
+                }
+                let path = recorder::segment_path(&base, Some(paths.len() + 1), "wav");
+                if path == input {
+                    return Err("Chunks would overwrite the input; pick another output directory".to_string());
+                }
+                sink = Some(WavSink::create(&path, spec)?);
+                if let Some(metadata) = metadata.as_ref() {
+                    let offset_ms = paths.len() as u64 * chunk_frames * 1000 / spec.sample_rate as u64;
+                    metadata::write(
+                        &path,
+                        &metadata::RecordingMetadata {
+                            started_at_unix_ms: metadata.started_at_unix_ms + offset_ms,
+                            discard_initial_ms: if paths.is_empty() { metadata.discard_initial_ms } else { 0 },
+                            gain_trajectory: None,
+                            ..metadata.clone()
+                        },
+                    )?;
+                }
+                paths.push(path.to_string_lossy().to_string());
+                left_in_chunk = chunk_frames;
+            }
+            let frames = (data.len() / channels).min(left_in_chunk as usize);
+            let (now, rest) = data.split_at(frames * channels);
+            if let Some(sink) = sink.as_mut() {
+                sink.write(now)?;
+            }
+            left_in_chunk -= frames as u64;
+            data = rest;
+        }
+    }
+    match sink {
+        Some(sink) => sink.finalize()?,
+        None => return Err("Recording has no audio".to_string()),
+    }
+    Ok(paths)
+}
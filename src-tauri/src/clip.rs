@@ -0,0 +1,107 @@
+use std::path::Path;
+use serde::Serialize;
+
+use crate::convert;
+use crate::decode;
+use crate::encode;
+use crate::format::OutputFormat;
+
+// Longer than any phone accepts as a ringtone
+const MAX_CLIP_SECS: f64 = 60.0;
+const DEFAULT_FADE_MS: u64 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipResult {
+    pub path: String,
+    pub format: OutputFormat,
+    pub duration_secs: f64,
+}
+
+// Cut `length_secs` from `input` at `start_secs`, fade both ends so the clip
+// loops without a click, and encode it (e.g. MP3 for phones)
+#[tauri::command]
+pub async fn make_clip(
+    input: String,
+    start_secs: f64,
+    length_secs: f64,
+    fade_ms: Option<u64>,
+    output_format: Option<OutputFormat>,
+    output: String,
+) -> Result<ClipResult, String> {
+    let input_path = Path::new(&input);
+    let output_path = Path::new(&output);
+    if !(start_secs >= 0.0 && length_secs > 0.0 && length_secs <= MAX_CLIP_SECS) {
+        return Err(format!(
+            "Clip must start at or after 0 and last between 0 and {} seconds",
+            MAX_CLIP_SECS
+        ));
+    }
+    let format = convert::resolve_output(input_path, output_path, output_format)?;
+
+    let mut source = decode::open(input_path)?;
+    let info = source.info();
+    let rate = info.sample_rate as f64;
+    let channels = info.channels as usize;
+    let start_frame = (start_secs * rate).round() as u64;
+    let clip_frames = (length_secs * rate).round() as u64;
+    let fits = |frames: u64| start_frame + clip_frames <= frames;
+    if info.frames.is_some_and(|frames| !fits(frames)) {
+        return Err(format!(
+            "Clip runs past the end of the recording ({:.2} s long)",
+            info.frames.unwrap_or(0) as f64 / rate
+        ));
+    }
+    let fade_frames = (fade_ms.unwrap_or(DEFAULT_FADE_MS) as f64 / 1000.0 * rate) as u64;
+    if fade_frames * 2 > clip_frames {
+        return Err("Fades can be at most half the clip length".to_string());
+    }
+
+    // The clip is short enough to hold, which also catches sources that
+    // don't declare their length
+    let mut clip = Vec::with_capacity(clip_frames as usize * channels);
+    let mut buffer = Vec::new();
+    let mut position = 0u64;
+    while position < start_frame + clip_frames {
+        let read = source.read_chunk(&mut buffer)? as u64;
+        if read == 0 {
+            break;
+        }
+        let keep_from = start_frame.saturating_sub(position).min(read);
+        let keep_to = (start_frame + clip_frames).saturating_sub(position).min(read);
+        if keep_from < keep_to {
+            clip.extend_from_slice(&buffer[keep_from as usize * channels..keep_to as usize * channels]);
+        }
+        position += read;
+    }
+    if !fits(position) {
+        return Err(format!("Clip runs past the end of the recording ({:.2} s long)", position as f64 / rate));
+    }
+
+    for (index, frame) in clip.chunks_exact_mut(channels).enumerate() {
+        let from_edge = (index as u64).min(clip_frames - 1 - index as u64);
+        if from_edge < fade_frames {
+            let gain = (from_edge as f32 + 0.5) / fade_frames as f32;
+            frame.iter_mut().for_each(|sample| *sample *= gain);
+        }
+    }
+
+    let mut sink = encode::create_sink(
+        output_path,
+        format,
+        convert::output_spec(&info, info.channels, info.sample_rate),
+        None,
+    )?;
+    sink.write(&clip)
+        .and_then(|_| sink.finalize())
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(output_path);
+        })?;
+    convert::copy_tags(input_path, output_path)?;
+
+    Ok(ClipResult {
+        path: output,
+        format,
+        duration_secs: clip_frames as f64 / rate,
+    })
+}
@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::convert;
+use crate::decode;
+use crate::encode;
+use crate::session::{self, Session};
+use crate::timeline;
+
+// Each cut dips to silence over this long either side so it never clicks
+const CUT_FADE_MS: u64 = 10;
+const MIN_KEEP_SECS: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CondenseSettings {
+    // Pauses between speech longer than this are shortened
+    pub max_gap_secs: f64,
+    // What is left of a shortened pause, split either side of the cut
+    pub keep_secs: f64,
+}
+
+impl Default for CondenseSettings {
+    fn default() -> Self {
+        Self {
+            max_gap_secs: 3.0,
+            keep_secs: 0.5,
+        }
+    }
+}
+
+impl CondenseSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.keep_secs.is_finite() && self.keep_secs >= MIN_KEEP_SECS) {
+            return Err(format!("Shortened pauses must keep at least {} s", MIN_KEEP_SECS));
+        }
+        if !(self.max_gap_secs.is_finite() && self.max_gap_secs >= self.keep_secs) {
+            return Err("Longest pause must be at least as long as what is kept of it".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CondenseResult {
+    pub path: String,
+    pub original_secs: f64,
+    pub condensed_secs: f64,
+    pub removed_secs: f64,
+    pub gaps_shortened: usize,
+}
+
+// Shorten every pause between speech longer than `max_gap_secs` to
+// `keep_secs` (half a second by default), e.g. to condense a meeting. Pauses
+// at the very start and end are left alone; trim handles those.
+#[tauri::command]
+pub async fn condense_silence(
+    input: String,
+    max_gap_secs: f64,
+    output: String,
+    keep_secs: Option<f64>,
+) -> Result<CondenseResult, String> {
+    let settings = CondenseSettings {
+        max_gap_secs,
+        keep_secs: keep_secs.unwrap_or(CondenseSettings::default().keep_secs),
+    };
+    condense(Path::new(&input), Path::new(&output), &settings)
+}
+
+// `recording.wav` gets `recording-condensed.wav`
+pub fn condensed_path(recording: &Path) -> PathBuf {
+    let stem = recording.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = recording.extension().map(|extension| extension.to_string_lossy().to_string()).unwrap_or_default();
+    recording.with_file_name(format!("{}-condensed.{}", stem, extension))
+}
+
+// Write a condensed copy next to a finished recording without holding up
+// stop; announced as "silence-condensed", or a session warning on failure
+pub fn spawn_condense(
+    app_handle: AppHandle,
+    session: Arc<Mutex<Option<Session>>>,
+    recording: PathBuf,
+    settings: CondenseSettings,
+) {
+    thread::spawn(move || match condense(&recording, &condensed_path(&recording), &settings) {
+        Ok(result) => {
+            let _ = app_handle.emit("silence-condensed", result);
+        }
+        Err(e) => session::update(&session, |session| {
+            session.warnings.push(format!("Failed to condense {}: {}", recording.display(), e));
+        }),
+    });
+}
+
+pub fn condense(input: &Path, output: &Path, settings: &CondenseSettings) -> Result<CondenseResult, String> {
+    settings.validate()?;
+    let format = convert::resolve_output(input, output, None)?;
+    let (original_secs, speech) = timeline::scan(input, true)?;
+
+    let mut source = decode::open(input)?;
+    let info = source.info();
+    let rate = info.sample_rate as f64;
+    let channels = info.channels as usize;
+    let half_keep = settings.keep_secs / 2.0;
+    // Frame ranges to drop, in order
+    let cuts: Vec<(u64, u64)> = speech
+        .windows(2)
+        .filter(|pair| pair[1].0 - pair[0].1 > settings.max_gap_secs)
+        .map(|pair| (((pair[0].1 + half_keep) * rate) as u64, ((pair[1].0 - half_keep) * rate) as u64))
+        .collect();
+    let fade_frames = (CUT_FADE_MS * info.sample_rate as u64 / 1000).max(1);
+
+    let mut sink = encode::create_sink(output, format, convert::output_spec(&info, info.channels, info.sample_rate), None)?;
+    let mut buffer = Vec::new();
+    let mut kept = Vec::new();
+    let mut position = 0u64;
+    let mut written = 0u64;
+    let mut next_cut = 0;
+    let result = (|| {
+        loop {
+            let read = source.read_chunk(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            kept.clear();
+            for frame in buffer.chunks_exact(channels) {
+                while cuts.get(next_cut).is_some_and(|&(_, end)| position >= end + fade_frames) {
+                    next_cut += 1;
+                }
+                let gain = match cuts.get(next_cut) {
+                    Some(&(start, end)) if position >= start && position < end => None,
+                    Some(&(start, _)) if position < start => {
+                        Some(((start - position) as f32 / fade_frames as f32).min(1.0))
+                    }
+                    Some(&(_, end)) => Some(((position - end) as f32 / fade_frames as f32).min(1.0)),
+                    None => Some(1.0),
+                };
+                if let Some(gain) = gain {
+                    kept.extend(frame.iter().map(|sample| sample * gain));
+                    written += 1;
+                }
+                position += 1;
+            }
+            sink.write(&kept)?;
+        }
+        sink.finalize()
+    })();
+    result.inspect_err(|_| {
+        let _ = std::fs::remove_file(output);
+    })?;
+    convert::copy_tags(input, output)?;
+
+    let condensed_secs = written as f64 / rate;
+    Ok(CondenseResult {
+        path: output.to_string_lossy().to_string(),
+        original_secs,
+        condensed_secs,
+        removed_secs: (position - written) as f64 / rate,
+        gaps_shortened: cuts.len(),
+    })
+}
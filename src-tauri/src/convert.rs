@@ -0,0 +1,180 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use hound::{SampleFormat, WavSpec};
+use serde::Serialize;
+use tauri::Emitter;
+
+use crate::decode::{self, PcmSource, SourceInfo};
+use crate::dsp::FilterChain;
+use crate::encode::{self, PcmSink};
+use crate::eq::EqCurve;
+use crate::format::{BitrateMode, OutputFormat};
+use crate::tags;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConvertProgress {
+    input: String,
+    processed_secs: f64,
+    // Missing when the input doesn't record its length
+    total_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertResult {
+    pub path: String,
+    pub format: OutputFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_secs: f64,
+    // Whether any tags were carried over from the input
+    pub tags_copied: bool,
+}
+
+// Decode any supported input and re-encode it as `format` (default: from the
+// output's extension), carrying the tags across and applying the optional EQ
+#[tauri::command]
+pub async fn convert(
+    app_handle: tauri::AppHandle,
+    input: String,
+    output: String,
+    format: Option<OutputFormat>,
+    bitrate: Option<BitrateMode>,
+    eq: Option<EqCurve>,
+) -> Result<ConvertResult, String> {
+    let input_path = Path::new(&input);
+    let output_path = Path::new(&output);
+    let format = resolve_output(input_path, output_path, format)?;
+    if let Some(mode) = bitrate {
+        mode.validate(format)?;
+    }
+
+    let mut source = decode::open(input_path)?;
+    let info = source.info();
+    let spec = output_spec(&info, info.channels, info.sample_rate);
+    let total_secs = info.frames.map(|frames| frames as f64 / info.sample_rate as f64);
+    let mut filters = match eq {
+        Some(curve) => Some(curve.build(info.sample_rate, info.channels as usize)?),
+        None => None,
+    };
+
+    let sink = encode::create_sink(output_path, format, spec, bitrate)?;
+    let mut last_progress = Instant::now();
+    let result = pump(source.as_mut(), filters.as_mut(), sink, |frames| {
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            let _ = app_handle.emit(
+                "convert-progress",
+                ConvertProgress {
+                    input: input.clone(),
+                    processed_secs: frames as f64 / info.sample_rate as f64,
+                    total_secs,
+                },
+            );
+        }
+    });
+    // Don't leave a truncated file behind
+    let frames = match result {
+        Ok(0) => Err("Input has no audio".to_string()),
+        other => other,
+    }
+    .inspect_err(|_| {
+        let _ = std::fs::remove_file(output_path);
+    })?;
+
+    let tags_copied = copy_tags(input_path, output_path)?;
+
+    Ok(ConvertResult {
+        path: output,
+        format,
+        sample_rate: info.sample_rate,
+        channels: info.channels,
+        duration_secs: frames as f64 / info.sample_rate as f64,
+        tags_copied,
+    })
+}
+
+// The output format, from `format` or the output's extension, after checking
+// that both ends of a conversion make sense
+pub fn resolve_output(input: &Path, output: &Path, format: Option<OutputFormat>) -> Result<OutputFormat, String> {
+    let extension_format = output
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(OutputFormat::from_extension);
+    let format = match (format, extension_format) {
+        (Some(format), Some(extension)) if format != extension => {
+            return Err(format!(
+                "Output extension doesn't match the {} format",
+                format.extension().to_uppercase()
+            ));
+        }
+        (Some(format), _) | (None, Some(format)) => format,
+        (None, None) => return Err("Give a format or an output extension of wav, aiff or mp3".to_string()),
+    };
+    format.ensure_available()?;
+    let input_canonical = std::fs::canonicalize(input).map_err(|e| format!("Failed to open input: {}", e))?;
+    if std::fs::canonicalize(output).is_ok_and(|output| output == input_canonical) {
+        return Err("Output must be a different file from the input".to_string());
+    }
+    if let Some(parent) = output.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        if !parent.is_dir() {
+            return Err(format!("Output directory {} does not exist", parent.display()));
+        }
+    }
+    Ok(format)
+}
+
+// Integer PCM for the output: 24-bit sources stay at 24 bits, everything else
+// becomes 16-bit
+pub fn output_spec(info: &SourceInfo, channels: u16, sample_rate: u32) -> WavSpec {
+    let bits_per_sample = match info.bits_per_sample {
+        Some(bits) if bits > 16 => 24,
+        _ => 16,
+    };
+    WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample,
+        sample_format: SampleFormat::Int,
+    }
+}
+
+// Tags map through the format-independent view, so e.g. ID3 becomes RIFF
+// INFO and ID3 in a WAV. Inputs lofty can't read simply have none.
+pub fn copy_tags(input: &Path, output: &Path) -> Result<bool, String> {
+    let input_tags = tags::read_tags(input).unwrap_or_default();
+    if !input_tags.has_any() {
+        return Ok(false);
+    }
+    tags::write_tags(output, &input_tags)?;
+    Ok(true)
+}
+
+// Stream every frame of `source` through `filters` into `sink`, reporting the
+// running frame count
+fn pump(
+    source: &mut dyn PcmSource,
+    mut filters: Option<&mut FilterChain>,
+    mut sink: Box<dyn PcmSink>,
+    mut progress: impl FnMut(u64),
+) -> Result<u64, String> {
+    let mut buffer = Vec::new();
+    let mut frames = 0u64;
+    loop {
+        let read = source.read_chunk(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        if let Some(filters) = filters.as_mut() {
+            filters.process_interleaved(&mut buffer);
+        }
+        sink.write(&buffer)?;
+        frames += read as u64;
+        progress(frames);
+    }
+    sink.finalize()?;
+    Ok(frames)
+}
@@ -0,0 +1,209 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::dsp::Emphasis;
+use crate::metadata;
+use crate::wav::WavStream;
+
+const CHUNK_FRAMES: usize = 4096;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SourceInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    // Depth of the stored samples, when the format has one
+    pub bits_per_sample: Option<u16>,
+    // Length in frames, when the container says
+    pub frames: Option<u64>,
+}
+
+// Reader for any supported input, yielding interleaved f32 frames
+pub trait PcmSource {
+    fn info(&self) -> SourceInfo;
+    // Fill `buffer` with the next frames; returns how many, 0 at end of input
+    fn read_chunk(&mut self, buffer: &mut Vec<f32>) -> Result<usize, String>;
+}
+
+impl PcmSource for WavStream {
+    fn info(&self) -> SourceInfo {
+        let spec = self.spec();
+        SourceInfo {
+            sample_rate: spec.sample_rate,
+            channels: spec.channels,
+            bits_per_sample: Some(spec.bits_per_sample),
+            frames: Some(self.frames() as u64),
+        }
+    }
+
+    fn read_chunk(&mut self, buffer: &mut Vec<f32>) -> Result<usize, String> {
+        WavStream::read_chunk(self, CHUNK_FRAMES, buffer)
+    }
+}
+
+// Undoes the pre-emphasis a recording was made with as it is read
+struct DeEmphasized {
+    source: Box<dyn PcmSource>,
+    emphasis: Emphasis,
+}
+
+impl PcmSource for DeEmphasized {
+    fn info(&self) -> SourceInfo {
+        self.source.info()
+    }
+
+    fn read_chunk(&mut self, buffer: &mut Vec<f32>) -> Result<usize, String> {
+        let read = self.source.read_chunk(buffer)?;
+        self.emphasis.process_interleaved(buffer);
+        Ok(read)
+    }
+}
+
+// Open `path` by its contents rather than its extension. WAV is always
+// readable; everything else needs the `decode` feature. A recording made
+// with pre-emphasis reads back flat.
+pub fn open(path: &Path) -> Result<Box<dyn PcmSource>, String> {
+    let source = open_format(path)?;
+    match metadata::emphasis(path) {
+        Some(coefficient) => {
+            let emphasis = Emphasis::de(coefficient, source.info().channels as usize);
+            Ok(Box::new(DeEmphasized { source, emphasis }))
+        }
+        None => Ok(source),
+    }
+}
+
+fn open_format(path: &Path) -> Result<Box<dyn PcmSource>, String> {
+    let mut head = [0u8; 64];
+    let len = File::open(path)
+        .and_then(|mut file| file.read(&mut head))
+        .map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let head = &head[..len];
+
+    if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WAVE") {
+        return Ok(Box::new(WavStream::open(path)?));
+    }
+    // Ogg Opus has no pure-Rust decoder to lean on
+    if head.starts_with(b"OggS") && head.windows(8).any(|window| window == b"OpusHead") {
+        return Err("Decoding Opus is not supported".to_string());
+    }
+    imp::open(path)
+}
+
+#[cfg(not(feature = "decode"))]
+mod imp {
+    use std::path::Path;
+    use super::PcmSource;
+
+    pub fn open(_path: &Path) -> Result<Box<dyn PcmSource>, String> {
+        Err("Decoding formats other than WAV is not compiled into this build".to_string())
+    }
+}
+
+#[cfg(feature = "decode")]
+mod imp {
+    use std::fs::File;
+    use std::path::Path;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{Decoder, DecoderOptions};
+    use symphonia::core::errors::Error;
+    use symphonia::core::formats::{FormatOptions, FormatReader};
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+    use super::{PcmSource, SourceInfo};
+
+    struct SymphoniaSource {
+        format: Box<dyn FormatReader>,
+        decoder: Box<dyn Decoder>,
+        track_id: u32,
+        info: SourceInfo,
+        samples: Option<SampleBuffer<f32>>,
+    }
+
+    pub fn open(path: &Path) -> Result<Box<dyn PcmSource>, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+            hint.with_extension(extension);
+        }
+        // Gapless trims the encoder delay and padding MP3 adds at each end
+        let format_options = FormatOptions {
+            enable_gapless: true,
+            ..Default::default()
+        };
+        let probed = symphonia::default::get_probe()
+            .format(&hint, stream, &format_options, &MetadataOptions::default())
+            .map_err(|e| format!("Unrecognized audio format: {}", e))?;
+        let format = probed.format;
+
+        let track = format.default_track().ok_or("Audio file has no audio track")?;
+        let params = &track.codec_params;
+        let sample_rate = params.sample_rate.ok_or("Audio track has no sample rate")?;
+        let channels = params.channels.map(|channels| channels.count() as u16).unwrap_or(0);
+        if channels == 0 {
+            return Err("Audio track has no channels".to_string());
+        }
+        let info = SourceInfo {
+            sample_rate,
+            channels,
+            bits_per_sample: params.bits_per_sample.map(|bits| bits as u16),
+            frames: params.n_frames,
+        };
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .map_err(|e| format!("Unsupported codec: {}", e))?;
+        let track_id = track.id;
+
+        Ok(Box::new(SymphoniaSource {
+            format,
+            decoder,
+            track_id,
+            info,
+            samples: None,
+        }))
+    }
+
+    impl PcmSource for SymphoniaSource {
+        fn info(&self) -> SourceInfo {
+            self.info
+        }
+
+        fn read_chunk(&mut self, buffer: &mut Vec<f32>) -> Result<usize, String> {
+            buffer.clear();
+            loop {
+                let packet = match self.format.next_packet() {
+                    Ok(packet) => packet,
+                    Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(0),
+                    Err(e) => return Err(format!("Failed to read audio file: {}", e)),
+                };
+                if packet.track_id() != self.track_id {
+                    continue;
+                }
+                let decoded = match self.decoder.decode(&packet) {
+                    Ok(decoded) => decoded,
+                    // A corrupt packet is skipped, as players do
+                    Err(Error::DecodeError(_)) => continue,
+                    Err(e) => return Err(format!("Failed to decode audio: {}", e)),
+                };
+                let spec = *decoded.spec();
+                if spec.rate != self.info.sample_rate || spec.channels.count() as u16 != self.info.channels {
+                    return Err("Sample rate or channel count changes mid-stream".to_string());
+                }
+                if decoded.frames() == 0 {
+                    continue;
+                }
+
+                let capacity = decoded.capacity() as u64;
+                let samples = match self.samples.as_mut() {
+                    Some(samples) if samples.capacity() as u64 >= capacity * spec.channels.count() as u64 => samples,
+                    _ => self.samples.insert(SampleBuffer::new(capacity, spec)),
+                };
+                samples.copy_interleaved_ref(decoded);
+                buffer.extend_from_slice(samples.samples());
+                return Ok(buffer.len() / self.info.channels as usize);
+            }
+        }
+    }
+}
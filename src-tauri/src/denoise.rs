@@ -0,0 +1,363 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoiseProfile {
+    pub sample_rate: u32,
+    pub fft_size: usize,
+    // Average magnitude per FFT bin (0..=fft_size/2), over all channels
+    pub magnitudes: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DenoiseOptions {
+    // Multiple of the noise magnitude subtracted from each bin
+    pub strength: f32,
+    // Most a bin will be attenuated, so quiet passages don't turn watery
+    pub max_reduction_db: f32,
+}
+
+impl Default for DenoiseOptions {
+    fn default() -> Self {
+        Self {
+            strength: 1.5,
+            max_reduction_db: 24.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DenoiseReport {
+    pub path: String,
+    pub input_rms_dbfs: f32,
+    pub output_rms_dbfs: f32,
+    // Energy taken out of the signal, input minus output level
+    pub removed_db: f32,
+}
+
+// Analyze noise-only audio into a profile, from `path` or, without one, by
+// recording `duration_secs` from the default input. Needs the `denoise` feature.
+#[tauri::command]
+pub async fn capture_noise_profile(
+    path: Option<String>,
+    duration_secs: Option<f64>,
+    output: String,
+) -> Result<NoiseProfile, String> {
+    imp::capture_noise_profile(path, duration_secs, output)
+}
+
+// Spectral subtraction of a saved noise profile. Needs the `denoise` feature.
+#[tauri::command]
+pub async fn denoise(
+    input: String,
+    noise_profile: String,
+    output: String,
+    options: Option<DenoiseOptions>,
+) -> Result<DenoiseReport, String> {
+    imp::denoise(input, noise_profile, output, options.unwrap_or_default())
+}
+
+#[cfg(not(feature = "denoise"))]
+mod imp {
+    use super::{DenoiseOptions, DenoiseReport, NoiseProfile};
+
+    const UNAVAILABLE: &str = "Noise reduction is not compiled into this build";
+
+    pub fn capture_noise_profile(
+        _path: Option<String>,
+        _duration_secs: Option<f64>,
+        _output: String,
+    ) -> Result<NoiseProfile, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub fn denoise(
+        _input: String,
+        _noise_profile: String,
+        _output: String,
+        _options: DenoiseOptions,
+    ) -> Result<DenoiseReport, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+}
+
+#[cfg(feature = "denoise")]
+mod imp {
+    use std::f64::consts::PI;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+    use cpal::traits::{DeviceTrait, StreamTrait};
+    use rustfft::num_complex::Complex;
+    use rustfft::{Fft, FftPlanner};
+
+    use super::{DenoiseOptions, DenoiseReport, NoiseProfile};
+    use crate::capture;
+    use crate::convert;
+    use crate::decode;
+    use crate::format::OutputFormat;
+    use crate::levels;
+    use crate::wav::{WavSink, WavStream};
+
+    const FFT_SIZE: usize = 2048;
+    const HOP: usize = FFT_SIZE / 2;
+    const MIN_LIVE_SECS: f64 = 0.5;
+    const MAX_LIVE_SECS: f64 = 30.0;
+
+    // Square-root Hann: applied on analysis and synthesis it sums to one at
+    // 50% overlap, so untouched bins reconstruct exactly
+    fn window() -> Vec<f64> {
+        (0..FFT_SIZE).map(|n| (PI * (n as f64 + 0.5) / FFT_SIZE as f64).sin()).collect()
+    }
+
+    pub fn capture_noise_profile(
+        path: Option<String>,
+        duration_secs: Option<f64>,
+        output: String,
+    ) -> Result<NoiseProfile, String> {
+        let (samples, channels, sample_rate) = match path {
+            Some(path) => read_all(Path::new(&path))?,
+            None => record(duration_secs.unwrap_or(2.0))?,
+        };
+        let frames = samples.len() / channels.max(1);
+        if frames < FFT_SIZE {
+            return Err(format!("Noise sample must be at least {} frames long", FFT_SIZE));
+        }
+
+        let window = window();
+        let fft = FftPlanner::new().plan_fft_forward(FFT_SIZE);
+        let mut sums = vec![0.0f64; FFT_SIZE / 2 + 1];
+        let mut count = 0usize;
+        let mut buffer = vec![Complex::new(0.0, 0.0); FFT_SIZE];
+        for channel in 0..channels {
+            let mut start = 0;
+            while start + FFT_SIZE <= frames {
+                for (n, bin) in buffer.iter_mut().enumerate() {
+                    *bin = Complex::new(samples[(start + n) * channels + channel] as f64 * window[n], 0.0);
+                }
+                fft.process(&mut buffer);
+                for (sum, bin) in sums.iter_mut().zip(&buffer) {
+                    *sum += bin.norm();
+                }
+                count += 1;
+                start += HOP;
+            }
+        }
+
+        let profile = NoiseProfile {
+            sample_rate,
+            fft_size: FFT_SIZE,
+            magnitudes: sums.iter().map(|&sum| (sum / count as f64) as f32).collect(),
+        };
+        let json = serde_json::to_string(&profile).map_err(|e| format!("Failed to serialize noise profile: {}", e))?;
+        std::fs::write(&output, json).map_err(|e| format!("Failed to write noise profile: {}", e))?;
+        Ok(profile)
+    }
+
+    fn read_all(path: &Path) -> Result<(Vec<f32>, usize, u32), String> {
+        let mut source = decode::open(path)?;
+        let spec = source.info();
+        let mut samples = Vec::new();
+        let mut buffer = Vec::new();
+        while source.read_chunk(&mut buffer)? > 0 {
+            samples.extend_from_slice(&buffer);
+        }
+        Ok((samples, spec.channels as usize, spec.sample_rate))
+    }
+
+    fn record(duration_secs: f64) -> Result<(Vec<f32>, usize, u32), String> {
+        if !(MIN_LIVE_SECS..=MAX_LIVE_SECS).contains(&duration_secs) {
+            return Err(format!(
+                "Noise capture must last between {} and {} seconds",
+                MIN_LIVE_SECS, MAX_LIVE_SECS
+            ));
+        }
+        let device = capture::find_input_device(None)?;
+        let supported = device
+            .default_input_config()
+            .map_err(|e| format!("Failed to get default input config: {}", e))?;
+        let sample_format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let samples_ref = samples.clone();
+        let stream = capture::build_f32_input_stream(&device, &config, sample_format, move |data| {
+            if let Ok(mut samples) = samples_ref.lock() {
+                samples.extend_from_slice(data);
+            }
+        })?;
+        stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+        thread::sleep(Duration::from_secs_f64(duration_secs));
+        drop(stream);
+        let samples = std::mem::take(&mut *samples.lock().map_err(|e| e.to_string())?);
+        Ok((samples, config.channels as usize, config.sample_rate.0))
+    }
+
+    // Streaming short-time spectral subtraction for one channel
+    struct Subtractor {
+        forward: Arc<dyn Fft<f64>>,
+        inverse: Arc<dyn Fft<f64>>,
+        window: Arc<Vec<f64>>,
+        // Magnitude subtracted from each bin, and the lowest gain allowed
+        noise: Arc<Vec<f64>>,
+        floor: f64,
+        input: Vec<f64>,
+        overlap: Vec<f64>,
+        buffer: Vec<Complex<f64>>,
+    }
+
+    impl Subtractor {
+        // Takes HOP new samples and returns the HOP samples that are now final
+        fn process_hop(&mut self, hop: &[f64], out: &mut Vec<f64>) {
+            self.input.drain(..HOP);
+            self.input.extend_from_slice(hop);
+            for (n, bin) in self.buffer.iter_mut().enumerate() {
+                *bin = Complex::new(self.input[n] * self.window[n], 0.0);
+            }
+            self.forward.process(&mut self.buffer);
+            for (index, bin) in self.buffer.iter_mut().enumerate() {
+                // Bins above Nyquist mirror the ones below
+                let noise = self.noise[index.min(FFT_SIZE - index)];
+                let magnitude = bin.norm();
+                let gain = if magnitude > 0.0 {
+                    (1.0 - noise / magnitude).max(self.floor)
+                } else {
+                    self.floor
+                };
+                *bin *= gain;
+            }
+            self.inverse.process(&mut self.buffer);
+            for (n, bin) in self.buffer.iter().enumerate() {
+                self.overlap[n] += bin.re / FFT_SIZE as f64 * self.window[n];
+            }
+            out.extend_from_slice(&self.overlap[..HOP]);
+            self.overlap.drain(..HOP);
+            self.overlap.resize(FFT_SIZE, 0.0);
+        }
+    }
+
+    pub fn denoise(
+        input: String,
+        noise_profile: String,
+        output: String,
+        options: DenoiseOptions,
+    ) -> Result<DenoiseReport, String> {
+        let json = std::fs::read_to_string(&noise_profile).map_err(|e| format!("Failed to read noise profile: {}", e))?;
+        let profile: NoiseProfile =
+            serde_json::from_str(&json).map_err(|e| format!("Failed to parse noise profile: {}", e))?;
+        if profile.fft_size != FFT_SIZE || profile.magnitudes.len() != FFT_SIZE / 2 + 1 {
+            return Err("Noise profile was made with a different analysis size".to_string());
+        }
+        if !(options.strength > 0.0 && options.max_reduction_db >= 0.0) {
+            return Err("Strength must be positive and the maximum reduction non-negative".to_string());
+        }
+
+        // Also refuses to write over the input
+        convert::resolve_output(Path::new(&input), Path::new(&output), Some(OutputFormat::Wav))?;
+        let spec = WavStream::open(Path::new(&input))?.spec();
+        // Written flat, so the output needs no sidecar to be read right
+        let mut source = decode::open(Path::new(&input))?;
+        if spec.sample_rate != profile.sample_rate {
+            return Err(format!(
+                "Noise profile is for {} Hz but the recording is {} Hz",
+                profile.sample_rate, spec.sample_rate
+            ));
+        }
+        let channels = spec.channels as usize;
+
+        let mut planner = FftPlanner::new();
+        let forward = planner.plan_fft_forward(FFT_SIZE);
+        let inverse = planner.plan_fft_inverse(FFT_SIZE);
+        let window = Arc::new(window());
+        let noise = Arc::new(
+            profile
+                .magnitudes
+                .iter()
+                .map(|&magnitude| magnitude as f64 * options.strength as f64)
+                .collect::<Vec<f64>>(),
+        );
+        let floor = 10f64.powf(-options.max_reduction_db as f64 / 20.0);
+        let mut subtractors: Vec<Subtractor> = (0..channels)
+            .map(|_| Subtractor {
+                forward: forward.clone(),
+                inverse: inverse.clone(),
+                window: window.clone(),
+                noise: noise.clone(),
+                floor,
+                input: vec![0.0; FFT_SIZE],
+                overlap: vec![0.0; FFT_SIZE],
+                buffer: vec![Complex::new(0.0, 0.0); FFT_SIZE],
+            })
+            .collect();
+
+        let mut sink = WavSink::create(Path::new(&output), spec)?;
+        let total_frames = source.info().frames.unwrap_or(0) as usize;
+        // Output lags the input by this many frames
+        let latency = FFT_SIZE - HOP;
+        let mut pending: Vec<Vec<f64>> = vec![Vec::new(); channels];
+        let mut produced: Vec<Vec<f64>> = vec![Vec::new(); channels];
+        let mut to_skip = latency;
+        let mut written = 0usize;
+        let mut input_energy = 0.0f64;
+        let mut output_energy = 0.0f64;
+        let mut buffer = Vec::new();
+        let mut interleaved = Vec::new();
+        let mut exhausted = false;
+
+        while written < total_frames {
+            if !exhausted && source.read_chunk(&mut buffer)? == 0 {
+                exhausted = true;
+            }
+            if exhausted {
+                // Flush the tail with silence
+                buffer.clear();
+                buffer.resize(HOP * channels, 0.0);
+            } else {
+                input_energy += buffer.iter().map(|&s| (s as f64).powi(2)).sum::<f64>();
+            }
+            for frame in buffer.chunks_exact(channels) {
+                for (pending, &sample) in pending.iter_mut().zip(frame) {
+                    pending.push(sample as f64);
+                }
+            }
+            while pending[0].len() >= HOP {
+                for (channel, subtractor) in subtractors.iter_mut().enumerate() {
+                    let hop: Vec<f64> = pending[channel].drain(..HOP).collect();
+                    subtractor.process_hop(&hop, &mut produced[channel]);
+                }
+            }
+
+            let skip = to_skip.min(produced[0].len());
+            for produced in produced.iter_mut() {
+                produced.drain(..skip);
+            }
+            to_skip -= skip;
+            let ready = produced[0].len().min(total_frames - written);
+            interleaved.clear();
+            for index in 0..ready {
+                for produced in &produced {
+                    interleaved.push(produced[index] as f32);
+                }
+            }
+            for produced in produced.iter_mut() {
+                produced.drain(..ready);
+            }
+            output_energy += interleaved.iter().map(|&s| (s as f64).powi(2)).sum::<f64>();
+            sink.write(&interleaved)?;
+            written += ready;
+        }
+        sink.finalize()?;
+
+        let samples = (total_frames * channels).max(1) as f64;
+        let input_rms_dbfs = levels::amplitude_to_dbfs((input_energy / samples).sqrt() as f32);
+        let output_rms_dbfs = levels::amplitude_to_dbfs((output_energy / samples).sqrt() as f32);
+        Ok(DenoiseReport {
+            path: output,
+            input_rms_dbfs,
+            output_rms_dbfs,
+            removed_db: input_rms_dbfs - output_rms_dbfs,
+        })
+    }
+}
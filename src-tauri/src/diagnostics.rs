@@ -0,0 +1,166 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::Serialize;
+use tauri::{Manager, State};
+
+use crate::capture;
+use crate::levels::{self, BlockRms, ChannelLevels};
+use crate::wav::{self, WavSink};
+use crate::RecordingState;
+
+const MIN_TEST_SECS: f64 = 0.5;
+const MAX_TEST_SECS: f64 = 10.0;
+const BLOCK_MS: u32 = 50;
+// Quietest blocks are taken as the noise floor
+const NOISE_FLOOR_PERCENTILE: f32 = 0.1;
+// Peaks below this mean nothing but digital silence reached us
+const SILENCE_DBFS: f32 = -70.0;
+// Required headroom between peak and noise floor to count as real signal
+const MIN_SIGNAL_MARGIN_DB: f32 = 12.0;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MicrophoneTest {
+    pub working: bool,
+    pub device: String,
+    pub peak_dbfs: f32,
+    pub noise_floor_dbfs: f32,
+    pub captured_samples: u64,
+    pub clip_path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawDeviceConfig {
+    pub device: String,
+    pub sample_format: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    // None when the host reports the buffer size as unknown
+    pub min_buffer_size: Option<u32>,
+    pub max_buffer_size: Option<u32>,
+}
+
+// The input device's default config exactly as cpal reports it, before it is
+// turned into a StreamConfig, for debugging format negotiation
+#[tauri::command]
+pub async fn raw_default_config(device_name: Option<String>) -> Result<RawDeviceConfig, String> {
+    let device = capture::find_input_device(device_name.as_deref())?;
+    let name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Device {} has no default input config: {}", name, e))?;
+    let (min_buffer_size, max_buffer_size) = match *supported.buffer_size() {
+        cpal::SupportedBufferSize::Range { min, max } => (Some(min), Some(max)),
+        cpal::SupportedBufferSize::Unknown => (None, None),
+    };
+    Ok(RawDeviceConfig {
+        device: name,
+        sample_format: supported.sample_format().to_string(),
+        sample_rate: supported.sample_rate().0,
+        channels: supported.channels(),
+        min_buffer_size,
+        max_buffer_size,
+    })
+}
+
+// Record a short clip and judge whether the microphone picked up real signal
+#[tauri::command]
+pub async fn test_microphone(
+    app_handle: tauri::AppHandle,
+    state: State<'_, RecordingState>,
+    duration_secs: f64,
+    device_name: Option<String>,
+) -> Result<MicrophoneTest, String> {
+    if !(MIN_TEST_SECS..=MAX_TEST_SECS).contains(&duration_secs) {
+        return Err(format!(
+            "Test duration must be between {} and {} seconds",
+            MIN_TEST_SECS, MAX_TEST_SECS
+        ));
+    }
+    if *state.is_recording.lock().map_err(|e| e.to_string())? {
+        return Err("Cannot test the microphone while recording".to_string());
+    }
+
+    let app_data_dir = app_handle.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let clip_path = app_data_dir.join("microphone-test.wav");
+
+    let device = capture::find_input_device(device_name.as_deref())?;
+    let name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get default input config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    let channels = config.channels as usize;
+    let block_frames = (config.sample_rate.0 * BLOCK_MS / 1000) as usize;
+
+    struct Capture {
+        sink: Option<WavSink>,
+        levels: ChannelLevels,
+        blocks: BlockRms,
+        error: Option<String>,
+    }
+    let capture = Arc::new(Mutex::new(Capture {
+        sink: Some(WavSink::create(&clip_path, wav::pcm16_spec(config.channels, config.sample_rate.0))?),
+        levels: ChannelLevels::new(channels),
+        blocks: BlockRms::new(block_frames, channels),
+        error: None,
+    }));
+
+    let capture_ref = capture.clone();
+    let stream = capture::build_f32_input_stream(&device, &config, sample_format, move |data| {
+        if let Ok(mut capture) = capture_ref.lock() {
+            if let Some(Err(e)) = capture.sink.as_mut().map(|sink| sink.write(data)) {
+                capture.error.get_or_insert(e);
+            }
+            capture.levels.push_interleaved(data);
+            capture.blocks.push_interleaved(data);
+        }
+    })?;
+    stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+    thread::sleep(Duration::from_secs_f64(duration_secs));
+    drop(stream);
+
+    let mut capture = capture.lock().map_err(|e| e.to_string())?;
+    if let Some(e) = capture.error.take() {
+        return Err(e);
+    }
+    if let Some(sink) = capture.sink.take() {
+        sink.finalize()?;
+    }
+
+    let captured_samples = capture.levels.frames() * channels as u64;
+    let peak_dbfs = capture
+        .levels
+        .peak_dbfs()
+        .into_iter()
+        .fold(levels::MIN_DBFS, f32::max);
+    let noise_floor_dbfs = levels::percentile_dbfs(capture.blocks.blocks_dbfs(), NOISE_FLOOR_PERCENTILE);
+
+    let (working, message) = if captured_samples == 0 {
+        (false, "No audio was captured; the device may be in use or blocked".to_string())
+    } else if peak_dbfs <= SILENCE_DBFS {
+        (false, "Only silence was captured; check that the microphone is unmuted".to_string())
+    } else if peak_dbfs - noise_floor_dbfs < MIN_SIGNAL_MARGIN_DB {
+        (false, "Signal barely rises above the noise floor; speak during the test or move closer".to_string())
+    } else {
+        (true, "Microphone is working".to_string())
+    };
+
+    Ok(MicrophoneTest {
+        working,
+        device: name,
+        peak_dbfs,
+        noise_floor_dbfs,
+        captured_samples,
+        clip_path: clip_path.to_string_lossy().to_string(),
+        message,
+    })
+}
@@ -0,0 +1,6 @@
+use std::path::Path;
+
+// Space available to this process on the volume holding `path`
+pub fn available_space(path: &Path) -> Result<u64, String> {
+    fs2::available_space(path).map_err(|e| format!("Failed to check free disk space: {}", e))
+}
@@ -0,0 +1,457 @@
+use std::f64::consts::PI;
+
+// Second-order IIR section (RBJ audio EQ cookbook), transposed direct form II
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    fn from_coefficients(b0: f64, b1: f64, b2: f64, a0: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    // Angular frequency and bandwidth term shared by every design
+    fn prewarp(sample_rate: u32, frequency: f32, q: f32) -> (f64, f64) {
+        let w0 = 2.0 * PI * frequency as f64 / sample_rate as f64;
+        (w0, w0.sin() / (2.0 * q as f64))
+    }
+
+    pub fn peaking(sample_rate: u32, frequency: f32, gain_db: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::prewarp(sample_rate, frequency, q);
+        let a = 10f64.powf(gain_db as f64 / 40.0);
+        let cos = w0.cos();
+        Self::from_coefficients(
+            1.0 + alpha * a,
+            -2.0 * cos,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos,
+            1.0 - alpha / a,
+        )
+    }
+
+    pub fn low_shelf(sample_rate: u32, frequency: f32, gain_db: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::prewarp(sample_rate, frequency, q);
+        let a = 10f64.powf(gain_db as f64 / 40.0);
+        let cos = w0.cos();
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+        Self::from_coefficients(
+            a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+            a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha),
+            (a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+            (a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha,
+        )
+    }
+
+    pub fn high_shelf(sample_rate: u32, frequency: f32, gain_db: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::prewarp(sample_rate, frequency, q);
+        let a = 10f64.powf(gain_db as f64 / 40.0);
+        let cos = w0.cos();
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+        Self::from_coefficients(
+            a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha),
+            (a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha,
+        )
+    }
+
+    pub fn highpass(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::prewarp(sample_rate, frequency, q);
+        let cos = w0.cos();
+        Self::from_coefficients(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    pub fn lowpass(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let (w0, alpha) = Self::prewarp(sample_rate, frequency, q);
+        let cos = w0.cos();
+        Self::from_coefficients(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    pub fn process(&mut self, input: f32) -> f32 {
+        let x = input as f64;
+        let y = self.b0 * x + self.z1;
+        self.z1 = self.b1 * x - self.a1 * y + self.z2;
+        self.z2 = self.b2 * x - self.a2 * y;
+        y as f32
+    }
+}
+
+// The same cascade of biquads run independently on every channel of an
+// interleaved signal
+#[derive(Debug, Clone)]
+pub struct FilterChain {
+    channels: Vec<Vec<Biquad>>,
+}
+
+impl FilterChain {
+    pub fn new(stages: &[Biquad], channels: usize) -> Self {
+        Self {
+            channels: vec![stages.to_vec(); channels],
+        }
+    }
+
+    pub fn process_interleaved(&mut self, data: &mut [f32]) {
+        let channels = self.channels.len();
+        if channels == 0 {
+            return;
+        }
+        for frame in data.chunks_exact_mut(channels) {
+            for (sample, stages) in frame.iter_mut().zip(self.channels.iter_mut()) {
+                for stage in stages.iter_mut() {
+                    *sample = stage.process(*sample);
+                }
+            }
+        }
+    }
+}
+
+// First-order emphasis run independently on every channel of an interleaved
+// signal. Pre-emphasis y[n] = x[n] - a*x[n-1] lifts the highs; de-emphasis
+// y[n] = x[n] + a*y[n-1] is its exact inverse.
+#[derive(Debug, Clone)]
+pub struct Emphasis {
+    coefficient: f32,
+    inverse: bool,
+    // Last input (pre) or output (de) sample per channel
+    previous: Vec<f32>,
+}
+
+impl Emphasis {
+    pub fn pre(coefficient: f32, channels: usize) -> Self {
+        Self {
+            coefficient,
+            inverse: false,
+            previous: vec![0.0; channels],
+        }
+    }
+
+    pub fn de(coefficient: f32, channels: usize) -> Self {
+        Self {
+            inverse: true,
+            ..Self::pre(coefficient, channels)
+        }
+    }
+
+    pub fn process_interleaved(&mut self, data: &mut [f32]) {
+        let channels = self.previous.len();
+        if channels == 0 {
+            return;
+        }
+        for frame in data.chunks_exact_mut(channels) {
+            for (sample, previous) in frame.iter_mut().zip(self.previous.iter_mut()) {
+                if self.inverse {
+                    *sample += self.coefficient * *previous;
+                    *previous = *sample;
+                } else {
+                    let input = *sample;
+                    *sample -= self.coefficient * *previous;
+                    *previous = input;
+                }
+            }
+        }
+    }
+}
+
+pub fn validate_emphasis(coefficient: f32) -> Result<(), String> {
+    if !(coefficient > 0.0 && coefficient < 1.0) {
+        return Err("Emphasis coefficient must be between 0 and 1".to_string());
+    }
+    Ok(())
+}
+
+// Slow automatic gain control. Follows the signal's RMS level and eases the
+// gain towards whatever brings it to the target: down quickly when it gets
+// loud, back up slowly. Quiet passages below the gate hold the gain rather
+// than having their noise floor pulled up.
+#[derive(Debug, Clone)]
+pub struct AutoGain {
+    channels: usize,
+    target: f32,
+    max_gain: f32,
+    gate: f32,
+    gain: f32,
+    // Smoothed mean square of the input
+    level: f32,
+    level_coefficient: f32,
+    attack_coefficient: f32,
+    release_coefficient: f32,
+}
+
+impl AutoGain {
+    const LEVEL_MS: f32 = 300.0;
+    const ATTACK_MS: f32 = 100.0;
+    const RELEASE_MS: f32 = 3_000.0;
+    const GATE_DBFS: f32 = -55.0;
+
+    pub fn new(sample_rate: u32, channels: usize, target_dbfs: f32, max_gain_db: f32) -> Self {
+        let coefficient = |ms: f32| 1.0 - (-1000.0 / (ms * sample_rate as f32)).exp();
+        let amplitude = |dbfs: f32| 10f32.powf(dbfs / 20.0);
+        Self {
+            channels,
+            target: amplitude(target_dbfs),
+            max_gain: amplitude(max_gain_db),
+            gate: amplitude(Self::GATE_DBFS),
+            gain: 1.0,
+            level: 0.0,
+            level_coefficient: coefficient(Self::LEVEL_MS),
+            attack_coefficient: coefficient(Self::ATTACK_MS),
+            release_coefficient: coefficient(Self::RELEASE_MS),
+        }
+    }
+
+    pub fn process_interleaved(&mut self, data: &mut [f32]) {
+        if self.channels == 0 {
+            return;
+        }
+        for frame in data.chunks_exact_mut(self.channels) {
+            let mean_square = frame.iter().map(|sample| sample * sample).sum::<f32>() / self.channels as f32;
+            self.level += (mean_square - self.level) * self.level_coefficient;
+            let rms = self.level.sqrt();
+            if rms > self.gate {
+                let wanted = (self.target / rms).min(self.max_gain);
+                let coefficient = if wanted < self.gain {
+                    self.attack_coefficient
+                } else {
+                    self.release_coefficient
+                };
+                self.gain += (wanted - self.gain) * coefficient;
+            }
+            frame.iter_mut().for_each(|sample| *sample = (*sample * self.gain).clamp(-1.0, 1.0));
+        }
+    }
+
+    pub fn gain_db(&self) -> f32 {
+        20.0 * self.gain.log10()
+    }
+}
+
+// Streaming linear-interpolation resampler for interleaved audio. Raising
+// the rate adds no aliasing; to lower it, low-pass the input first.
+#[derive(Debug, Clone)]
+pub struct LinearResampler {
+    channels: usize,
+    // Input frames advanced per output frame
+    step: f64,
+    // Read position, where 0 is the last frame of the previous buffer
+    position: f64,
+    last: Vec<f32>,
+}
+
+impl LinearResampler {
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        Self {
+            channels,
+            step: input_rate as f64 / output_rate as f64,
+            position: 1.0,
+            last: vec![0.0; channels],
+        }
+    }
+
+    pub fn process_interleaved(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        output.clear();
+        let channels = self.channels;
+        if channels == 0 {
+            return;
+        }
+        let frames = input.len() / channels;
+        while (self.position as usize) < frames {
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            let current = if index == 0 {
+                &self.last[..]
+            } else {
+                &input[(index - 1) * channels..index * channels]
+            };
+            let next = &input[index * channels..(index + 1) * channels];
+            output.extend(current.iter().zip(next).map(|(&a, &b)| a + (b - a) * fraction));
+            self.position += self.step;
+        }
+        if frames > 0 {
+            self.position -= frames as f64;
+            self.last.copy_from_slice(&input[(frames - 1) * channels..frames * channels]);
+        }
+    }
+}
+
+// Q of the two sections of a 4th-order Butterworth, used as the anti-alias
+// filter when lowering the rate, with its corner just under the new Nyquist
+const ANTI_ALIAS_Q: [f32; 2] = [0.5412, 1.3066];
+const ANTI_ALIAS_CORNER: f32 = 0.45;
+
+// LinearResampler behind the anti-alias low-pass it needs when lowering the rate
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    anti_alias: Option<FilterChain>,
+    resampler: LinearResampler,
+    filtered: Vec<f32>,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32, channels: usize) -> Self {
+        let anti_alias = (output_rate < input_rate).then(|| {
+            let corner = output_rate as f32 * ANTI_ALIAS_CORNER;
+            let sections = ANTI_ALIAS_Q.map(|q| Biquad::lowpass(input_rate, corner, q));
+            FilterChain::new(&sections, channels)
+        });
+        Self {
+            anti_alias,
+            resampler: LinearResampler::new(input_rate, output_rate, channels),
+            filtered: Vec::new(),
+        }
+    }
+
+    pub fn process_interleaved(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        match self.anti_alias.as_mut() {
+            Some(filter) => {
+                self.filtered.clear();
+                self.filtered.extend_from_slice(input);
+                filter.process_interleaved(&mut self.filtered);
+                self.resampler.process_interleaved(&self.filtered, output);
+            }
+            None => self.resampler.process_interleaved(input, output),
+        }
+    }
+}
+
+pub const MAX_CHANNELS: u16 = 8;
+
+// Maps interleaved frames from one channel count to another:
+// - to mono, every input channel is averaged
+// - from mono, the one channel is copied to every output
+// - fewer outputs than inputs, output i averages inputs i, i + out, ...
+// - more outputs than inputs, output i repeats input i % in
+#[derive(Debug, Clone)]
+pub struct ChannelMixer {
+    input: usize,
+    // Input channels feeding each output channel
+    sources: Vec<Vec<usize>>,
+}
+
+impl ChannelMixer {
+    pub fn new(input: u16, output: u16) -> Result<Self, String> {
+        if input == 0 || output == 0 || output > MAX_CHANNELS {
+            return Err(format!(
+                "Cannot map {} input channels to {}; output must have 1 to {} channels",
+                input, output, MAX_CHANNELS
+            ));
+        }
+        let (input, output) = (input as usize, output as usize);
+        let sources = (0..output)
+            .map(|channel| {
+                if output >= input {
+                    vec![channel % input]
+                } else {
+                    (channel..input).step_by(output).collect()
+                }
+            })
+            .collect();
+        Ok(Self { input, sources })
+    }
+
+    pub fn process_interleaved(&self, input: &[f32], output: &mut Vec<f32>) {
+        output.clear();
+        for frame in input.chunks_exact(self.input) {
+            for sources in &self.sources {
+                let sum: f32 = sources.iter().map(|&source| frame[source]).sum();
+                output.push(sum / sources.len() as f32);
+            }
+        }
+    }
+}
+
+// Mutes the signal while its peak stays below the threshold. Opens almost
+// at once and closes after a short hold, so word endings aren't chopped.
+#[derive(Debug, Clone)]
+pub struct NoiseGate {
+    channels: usize,
+    threshold: f32,
+    hold_frames: u32,
+    held: u32,
+    gain: f32,
+    open_coefficient: f32,
+    close_coefficient: f32,
+}
+
+impl NoiseGate {
+    const OPEN_MS: f32 = 1.0;
+    const HOLD_MS: f32 = 50.0;
+    const CLOSE_MS: f32 = 100.0;
+
+    pub fn new(sample_rate: u32, channels: usize, threshold_dbfs: f32) -> Self {
+        let coefficient = |ms: f32| 1.0 - (-1000.0 / (ms * sample_rate as f32)).exp();
+        Self {
+            channels,
+            threshold: 10f32.powf(threshold_dbfs / 20.0),
+            hold_frames: (Self::HOLD_MS * sample_rate as f32 / 1000.0) as u32,
+            held: 0,
+            gain: 0.0,
+            open_coefficient: coefficient(Self::OPEN_MS),
+            close_coefficient: coefficient(Self::CLOSE_MS),
+        }
+    }
+
+    pub fn process_interleaved(&mut self, data: &mut [f32]) {
+        if self.channels == 0 {
+            return;
+        }
+        for frame in data.chunks_exact_mut(self.channels) {
+            let peak = frame.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            if peak >= self.threshold {
+                self.held = self.hold_frames;
+            } else {
+                self.held = self.held.saturating_sub(1);
+            }
+            if self.held > 0 {
+                self.gain += (1.0 - self.gain) * self.open_coefficient;
+            } else {
+                self.gain -= self.gain * self.close_coefficient;
+            }
+            frame.iter_mut().for_each(|sample| *sample *= self.gain);
+        }
+    }
+}
+
+// Leaves samples below the knee alone and bends anything above it smoothly
+// towards full scale instead of clipping hard
+pub fn soft_clip(sample: f32, knee: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= knee {
+        return sample;
+    }
+    let range = 1.0 - knee;
+    (knee + range * ((magnitude - knee) / range).tanh()).copysign(sample)
+}
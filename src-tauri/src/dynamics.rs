@@ -0,0 +1,207 @@
+use std::path::Path;
+use serde::Serialize;
+
+use crate::decode;
+use crate::levels::{self, BlockRms, ChannelLevels};
+
+// The DR meter's block length and the share of loudest blocks it averages
+const DR_BLOCK_SECS: f64 = 3.0;
+const DR_LOUD_SHARE: f64 = 0.2;
+// Blocks below this are pauses and are left out of the loud/quiet spread
+const SILENCE_DBFS: f32 = -70.0;
+const QUIET_PERCENTILE: f32 = 0.1;
+const LOUD_PERCENTILE: f32 = 0.95;
+// DR values at or below this read as squashed, above the other as dynamic
+const COMPRESSED_MAX_DR: u32 = 7;
+const DYNAMIC_MIN_DR: u32 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DynamicsVerdict {
+    Compressed,
+    Moderate,
+    Dynamic,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelDynamics {
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    // Peak over RMS for the whole channel
+    pub crest_factor_db: f32,
+    // DR meter value: second-highest block peak over the RMS of the loudest
+    // fifth of blocks; None when the channel is silent
+    pub dr: Option<f32>,
+    // Level of loud and quiet passages, ignoring pauses
+    pub loud_dbfs: f32,
+    pub quiet_dbfs: f32,
+    pub range_db: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicRangeReport {
+    pub duration_secs: f64,
+    // In channel order
+    pub channels: Vec<ChannelDynamics>,
+    // Channel DR values averaged and rounded, as DR meters show it
+    pub dr: Option<u32>,
+    pub crest_factor_db: f32,
+    pub range_db: f32,
+    pub verdict: Option<DynamicsVerdict>,
+}
+
+// Peak and RMS of one channel's consecutive DR blocks
+struct DrBlocks {
+    block_frames: usize,
+    sum_squares: f64,
+    peak: f32,
+    frames: usize,
+    rms: Vec<f64>,
+    peaks: Vec<f32>,
+}
+
+impl DrBlocks {
+    fn new(block_frames: usize) -> Self {
+        Self {
+            block_frames: block_frames.max(1),
+            sum_squares: 0.0,
+            peak: 0.0,
+            frames: 0,
+            rms: Vec::new(),
+            peaks: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, sample: f32) {
+        self.sum_squares += (sample as f64) * (sample as f64);
+        self.peak = self.peak.max(sample.abs());
+        self.frames += 1;
+        if self.frames == self.block_frames {
+            self.close_block();
+        }
+    }
+
+    fn close_block(&mut self) {
+        self.rms.push((self.sum_squares / self.frames as f64).sqrt());
+        self.peaks.push(self.peak);
+        self.sum_squares = 0.0;
+        self.peak = 0.0;
+        self.frames = 0;
+    }
+
+    // A recording shorter than one block is measured as a single block
+    fn finish(&mut self) {
+        if self.rms.is_empty() && self.frames > 0 {
+            self.close_block();
+        }
+    }
+
+    fn dr(&self) -> Option<f32> {
+        let mut peaks = self.peaks.clone();
+        peaks.sort_by(|a, b| b.total_cmp(a));
+        let peak = *peaks.get(1).or(peaks.first())? as f64;
+        let mut rms = self.rms.clone();
+        rms.sort_by(|a, b| b.total_cmp(a));
+        let loud = ((rms.len() as f64 * DR_LOUD_SHARE).round() as usize).max(1);
+        // The DR meter scales RMS by √2 so a full-scale sine reads as 0 dB
+        let loud_rms = (2.0 * rms[..loud].iter().map(|rms| rms * rms).sum::<f64>() / loud as f64).sqrt();
+        if peak <= 0.0 || loud_rms <= 0.0 {
+            return None;
+        }
+        Some((20.0 * (peak / loud_rms).log10()) as f32)
+    }
+
+    fn blocks_dbfs(&self) -> Vec<f32> {
+        self.rms.iter().map(|&rms| levels::amplitude_to_dbfs(rms as f32)).collect()
+    }
+}
+
+// Spread between loud and quiet blocks, leaving out pauses
+fn spread(blocks_dbfs: &[f32]) -> (f32, f32) {
+    let audible: Vec<f32> = blocks_dbfs.iter().copied().filter(|&level| level > SILENCE_DBFS).collect();
+    (
+        levels::percentile_dbfs(&audible, LOUD_PERCENTILE),
+        levels::percentile_dbfs(&audible, QUIET_PERCENTILE),
+    )
+}
+
+// How far apart the loud and quiet parts of a recording are, per channel and
+// overall, as a DR value, crest factor and loud/quiet spread. Squashed
+// masters read low on all three.
+#[tauri::command]
+pub async fn dynamic_range(path: String) -> Result<DynamicRangeReport, String> {
+    analyze(Path::new(&path))
+}
+
+pub fn analyze(path: &Path) -> Result<DynamicRangeReport, String> {
+    let mut source = decode::open(path)?;
+    let info = source.info();
+    let channels = info.channels as usize;
+    let block_frames = (info.sample_rate as f64 * DR_BLOCK_SECS) as usize;
+    let mut levels = ChannelLevels::new(channels);
+    let mut blocks = BlockRms::new(block_frames, channels);
+    let mut dr_blocks: Vec<DrBlocks> = (0..channels).map(|_| DrBlocks::new(block_frames)).collect();
+    let mut buffer = Vec::new();
+    while source.read_chunk(&mut buffer)? > 0 {
+        levels.push_interleaved(&buffer);
+        blocks.push_interleaved(&buffer);
+        for frame in buffer.chunks_exact(channels) {
+            for (sample, channel) in frame.iter().zip(dr_blocks.iter_mut()) {
+                channel.push(*sample);
+            }
+        }
+    }
+    if levels.frames() == 0 {
+        return Err("Recording has no audio".to_string());
+    }
+    dr_blocks.iter_mut().for_each(DrBlocks::finish);
+
+    let peaks = levels.peak_dbfs();
+    let rms = levels.rms_dbfs();
+    let channel_dynamics: Vec<ChannelDynamics> = dr_blocks
+        .iter()
+        .enumerate()
+        .map(|(index, channel)| {
+            let (loud_dbfs, quiet_dbfs) = spread(&channel.blocks_dbfs());
+            ChannelDynamics {
+                peak_dbfs: peaks[index],
+                rms_dbfs: rms[index],
+                crest_factor_db: peaks[index] - rms[index],
+                dr: channel.dr(),
+                loud_dbfs,
+                quiet_dbfs,
+                range_db: loud_dbfs - quiet_dbfs,
+            }
+        })
+        .collect();
+
+    let channel_drs: Vec<f32> = channel_dynamics.iter().filter_map(|channel| channel.dr).collect();
+    let dr = (!channel_drs.is_empty())
+        .then(|| (channel_drs.iter().sum::<f32>() / channel_drs.len() as f32).round().max(0.0) as u32);
+    let verdict = dr.map(|dr| match dr {
+        dr if dr <= COMPRESSED_MAX_DR => DynamicsVerdict::Compressed,
+        dr if dr >= DYNAMIC_MIN_DR => DynamicsVerdict::Dynamic,
+        _ => DynamicsVerdict::Moderate,
+    });
+
+    // Overall figures take the loudest channel's peak against the level of
+    // every channel together
+    let peak_dbfs = peaks.iter().copied().fold(levels::MIN_DBFS, f32::max);
+    let mean_square = rms
+        .iter()
+        .map(|&dbfs| 10f64.powf(dbfs as f64 / 10.0))
+        .sum::<f64>()
+        / channels as f64;
+    let rms_dbfs = levels::amplitude_to_dbfs(mean_square.sqrt() as f32);
+    let (loud_dbfs, quiet_dbfs) = spread(blocks.blocks_dbfs());
+    Ok(DynamicRangeReport {
+        duration_secs: levels.frames() as f64 / info.sample_rate as f64,
+        channels: channel_dynamics,
+        dr,
+        crest_factor_db: peak_dbfs - rms_dbfs,
+        range_db: loud_dbfs - quiet_dbfs,
+        verdict,
+    })
+}
@@ -0,0 +1,244 @@
+use std::path::Path;
+use hound::WavSpec;
+
+use crate::aiff::{self, AiffWriter};
+use crate::format::{BitrateMode, OutputFormat};
+use crate::wav::{WavSink, WavStream};
+
+const CHUNK_FRAMES: usize = 4096;
+pub const DEFAULT_BITRATE: BitrateMode = BitrateMode::Cbr(192);
+
+// Writer for the uncompressed formats, fed interleaved f32 frames
+pub trait PcmSink: Send {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String>;
+    fn finalize(self: Box<Self>) -> Result<(), String>;
+}
+
+impl PcmSink for WavSink {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        WavSink::write(self, samples)
+    }
+
+    fn finalize(self: Box<Self>) -> Result<(), String> {
+        WavSink::finalize(*self)
+    }
+}
+
+impl PcmSink for AiffWriter {
+    fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        AiffWriter::write(self, samples)
+    }
+
+    fn finalize(self: Box<Self>) -> Result<(), String> {
+        AiffWriter::finalize(*self)
+    }
+}
+
+// Open a writer for a PCM format, keeping as much of `spec` as the format allows
+pub fn create_pcm_sink(path: &Path, format: OutputFormat, spec: WavSpec) -> Result<Box<dyn PcmSink>, String> {
+    match format {
+        OutputFormat::Wav => Ok(Box::new(WavSink::create(path, spec)?)),
+        OutputFormat::Aiff => Ok(Box::new(AiffWriter::create(
+            path,
+            spec.channels,
+            spec.sample_rate,
+            aiff::bits_for(spec.bits_per_sample),
+        )?)),
+        _ => Err(format!("{} is not an uncompressed format", format.extension().to_uppercase())),
+    }
+}
+
+// Open a writer for any output format; `bitrate` only applies to MP3
+pub fn create_sink(
+    path: &Path,
+    format: OutputFormat,
+    spec: WavSpec,
+    bitrate: Option<BitrateMode>,
+) -> Result<Box<dyn PcmSink>, String> {
+    match format {
+        OutputFormat::Mp3 => mp3::create_sink(path, spec, bitrate.unwrap_or(DEFAULT_BITRATE)),
+        _ => create_pcm_sink(path, format, spec),
+    }
+}
+
+// Encode a finished WAV recording into `format`. Returns the average bitrate
+// of compressed output, which for VBR is only known once it is written.
+pub fn encode_wav(
+    input: &Path,
+    output: &Path,
+    format: OutputFormat,
+    bitrate: Option<BitrateMode>,
+) -> Result<Option<u32>, String> {
+    if format == OutputFormat::Wav {
+        return std::fs::copy(input, output)
+            .map(|_| None)
+            .map_err(|e| format!("Failed to copy recording: {}", e));
+    }
+    let mut stream = WavStream::open(input)?;
+    let spec = stream.spec();
+    let duration_secs = stream.frames() as f64 / spec.sample_rate as f64;
+    let mut sink = create_sink(output, format, spec, bitrate)?;
+    let mut buffer = Vec::new();
+    while stream.read_chunk(CHUNK_FRAMES, &mut buffer)? > 0 {
+        sink.write(&buffer)?;
+    }
+    sink.finalize()?;
+    if format.is_pcm() || duration_secs <= 0.0 {
+        return Ok(None);
+    }
+    let bytes = std::fs::metadata(output)
+        .map_err(|e| format!("Failed to read encoded file: {}", e))?
+        .len();
+    Ok(Some((bytes as f64 * 8.0 / duration_secs / 1000.0).round() as u32))
+}
+
+#[cfg(not(feature = "mp3"))]
+mod mp3 {
+    use std::path::Path;
+    use hound::WavSpec;
+    use crate::format::BitrateMode;
+    use super::PcmSink;
+
+    pub fn create_sink(_path: &Path, _spec: WavSpec, _mode: BitrateMode) -> Result<Box<dyn PcmSink>, String> {
+        Err("MP3 support is not compiled into this build".to_string())
+    }
+}
+
+#[cfg(feature = "mp3")]
+mod mp3 {
+    use std::fs::File;
+    use std::io::{BufWriter, Seek, SeekFrom, Write};
+    use std::path::Path;
+    use hound::WavSpec;
+    use mp3lame_encoder::{
+        ffi, max_required_buffer_size, Bitrate, Builder, Encoder, FlushGap, InterleavedPcm, MonoPcm, Quality, VbrMode,
+    };
+    use crate::format::BitrateMode;
+    use super::PcmSink;
+
+    fn bitrate(kbps: u32) -> Result<Bitrate, String> {
+        Ok(match kbps {
+            8 => Bitrate::Kbps8,
+            16 => Bitrate::Kbps16,
+            24 => Bitrate::Kbps24,
+            32 => Bitrate::Kbps32,
+            40 => Bitrate::Kbps40,
+            48 => Bitrate::Kbps48,
+            64 => Bitrate::Kbps64,
+            80 => Bitrate::Kbps80,
+            96 => Bitrate::Kbps96,
+            112 => Bitrate::Kbps112,
+            128 => Bitrate::Kbps128,
+            160 => Bitrate::Kbps160,
+            192 => Bitrate::Kbps192,
+            224 => Bitrate::Kbps224,
+            256 => Bitrate::Kbps256,
+            320 => Bitrate::Kbps320,
+            _ => return Err(format!("Unsupported MP3 bitrate: {} kbps", kbps)),
+        })
+    }
+
+    fn vbr_quality(quality: u8) -> Result<Quality, String> {
+        Ok(match quality {
+            0 => Quality::Best,
+            1 => Quality::SecondBest,
+            2 => Quality::NearBest,
+            3 => Quality::VeryNice,
+            4 => Quality::Nice,
+            5 => Quality::Good,
+            6 => Quality::Decent,
+            7 => Quality::Ok,
+            8 => Quality::SecondWorst,
+            9 => Quality::Worst,
+            _ => return Err(format!("Unsupported VBR quality: {}", quality)),
+        })
+    }
+
+    // Streaming LAME encoder; input is quantized to 16 bits on the way in
+    struct Mp3Sink {
+        encoder: Encoder,
+        writer: BufWriter<File>,
+        channels: u16,
+        pcm: Vec<i16>,
+        mp3_out: Vec<u8>,
+    }
+
+    pub fn create_sink(path: &Path, spec: WavSpec, mode: BitrateMode) -> Result<Box<dyn PcmSink>, String> {
+        if spec.channels == 0 || spec.channels > 2 {
+            return Err(format!("MP3 supports mono or stereo only, got {} channels", spec.channels));
+        }
+
+        let configure = |e: mp3lame_encoder::BuildError| format!("Failed to configure MP3 encoder: {}", e);
+        let mut builder = Builder::new().ok_or("Failed to create MP3 encoder")?;
+        builder.set_num_channels(spec.channels as u8).map_err(configure)?;
+        builder.set_sample_rate(spec.sample_rate).map_err(configure)?;
+        match mode {
+            BitrateMode::Cbr(kbps) => builder.set_brate(bitrate(kbps)?).map_err(configure)?,
+            BitrateMode::Vbr { quality } => {
+                builder.set_vbr_mode(VbrMode::Mtrh).map_err(configure)?;
+                builder.set_vbr_quality(vbr_quality(quality)?).map_err(configure)?;
+            }
+            BitrateMode::Abr(kbps) => {
+                builder.set_vbr_mode(VbrMode::Abr).map_err(configure)?;
+                // The safe wrapper has no setter for the ABR target
+                let status = unsafe { ffi::lame_set_VBR_mean_bitrate_kbps(builder.as_ptr(), kbps as _) };
+                if status != 0 {
+                    return Err(format!("Unsupported ABR bitrate: {} kbps", kbps));
+                }
+            }
+        }
+        builder.set_quality(Quality::Best).map_err(configure)?;
+        let encoder = builder.build().map_err(configure)?;
+
+        let file = File::create(path).map_err(|e| format!("Failed to create MP3 file: {}", e))?;
+        Ok(Box::new(Mp3Sink {
+            encoder,
+            writer: BufWriter::new(file),
+            channels: spec.channels,
+            pcm: Vec::new(),
+            mp3_out: Vec::new(),
+        }))
+    }
+
+    impl PcmSink for Mp3Sink {
+        fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+            self.pcm.clear();
+            self.pcm.extend(samples.iter().map(|&sample| (sample * 32_768.0).clamp(-32_768.0, 32_767.0) as i16));
+
+            self.mp3_out.clear();
+            self.mp3_out.reserve(max_required_buffer_size(self.pcm.len()));
+            let encoded = if self.channels == 1 {
+                self.encoder.encode_to_vec(MonoPcm(&self.pcm), &mut self.mp3_out)
+            } else {
+                self.encoder.encode_to_vec(InterleavedPcm(&self.pcm), &mut self.mp3_out)
+            };
+            encoded.map_err(|e| format!("Failed to encode MP3: {}", e))?;
+            self.writer
+                .write_all(&self.mp3_out)
+                .map_err(|e| format!("Failed to write MP3 file: {}", e))
+        }
+
+        fn finalize(mut self: Box<Self>) -> Result<(), String> {
+            self.mp3_out.clear();
+            self.mp3_out.reserve(max_required_buffer_size(0));
+            self.encoder
+                .flush_to_vec::<FlushGap>(&mut self.mp3_out)
+                .map_err(|e| format!("Failed to flush MP3 encoder: {}", e))?;
+            self.writer
+                .write_all(&self.mp3_out)
+                .map_err(|e| format!("Failed to write MP3 file: {}", e))?;
+
+            // LAME leaves room for a Xing/Info frame at the start; filling it in
+            // gives players the real length of VBR audio and the encoder delay
+            self.mp3_out.clear();
+            self.mp3_out.reserve(self.encoder.lame_tag_size());
+            if self.encoder.lame_tag_encode_to_vec(&mut self.mp3_out).is_some() {
+                self.writer
+                    .seek(SeekFrom::Start(0))
+                    .and_then(|_| self.writer.write_all(&self.mp3_out))
+                    .map_err(|e| format!("Failed to write MP3 file: {}", e))?;
+            }
+            self.writer.flush().map_err(|e| format!("Failed to write MP3 file: {}", e))
+        }
+    }
+}
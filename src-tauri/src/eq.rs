@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::{Biquad, FilterChain};
+
+const MIN_FREQUENCY_HZ: f32 = 20.0;
+const MAX_FREQUENCY_HZ: f32 = 20_000.0;
+const MAX_GAIN_DB: f32 = 24.0;
+const MIN_Q: f32 = 0.1;
+const MAX_Q: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EqBand {
+    pub frequency_hz: f32,
+    pub gain_db: f32,
+    pub q: f32,
+}
+
+impl EqBand {
+    fn validate(&self, name: &str, sample_rate: u32) -> Result<(), String> {
+        // Stay clear of Nyquist, where the cookbook designs stop behaving
+        let max_frequency = MAX_FREQUENCY_HZ.min(sample_rate as f32 * 0.45);
+        if !(MIN_FREQUENCY_HZ..=max_frequency).contains(&self.frequency_hz) {
+            return Err(format!(
+                "{} frequency must be between {} and {:.0} Hz",
+                name, MIN_FREQUENCY_HZ, max_frequency
+            ));
+        }
+        if !(-MAX_GAIN_DB..=MAX_GAIN_DB).contains(&self.gain_db) {
+            return Err(format!("{} gain must be within ±{} dB", name, MAX_GAIN_DB));
+        }
+        if !(MIN_Q..=MAX_Q).contains(&self.q) {
+            return Err(format!("{} Q must be between {} and {}", name, MIN_Q, MAX_Q));
+        }
+        Ok(())
+    }
+}
+
+// Three-band voice EQ: low shelf, mid bell and high shelf, each optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EqCurve {
+    pub low_shelf: Option<EqBand>,
+    pub mid: Option<EqBand>,
+    pub high_shelf: Option<EqBand>,
+}
+
+impl EqCurve {
+    pub fn validate(&self, sample_rate: u32) -> Result<(), String> {
+        if let Some(band) = &self.low_shelf {
+            band.validate("Low shelf", sample_rate)?;
+        }
+        if let Some(band) = &self.mid {
+            band.validate("Mid band", sample_rate)?;
+        }
+        if let Some(band) = &self.high_shelf {
+            band.validate("High shelf", sample_rate)?;
+        }
+        Ok(())
+    }
+
+    // Cascaded biquads with independent state per channel
+    pub fn build(&self, sample_rate: u32, channels: usize) -> Result<FilterChain, String> {
+        self.validate(sample_rate)?;
+        let mut stages = Vec::new();
+        if let Some(band) = self.low_shelf {
+            stages.push(Biquad::low_shelf(sample_rate, band.frequency_hz, band.gain_db, band.q));
+        }
+        if let Some(band) = self.mid {
+            stages.push(Biquad::peaking(sample_rate, band.frequency_hz, band.gain_db, band.q));
+        }
+        if let Some(band) = self.high_shelf {
+            stages.push(Biquad::high_shelf(sample_rate, band.frequency_hz, band.gain_db, band.q));
+        }
+        Ok(FilterChain::new(&stages, channels))
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+use crate::convert;
+use crate::dsp::Emphasis;
+use crate::encode;
+use crate::eq::EqCurve;
+use crate::format::{BitrateMode, OutputFormat};
+use crate::metadata;
+use crate::wav::WavStream;
+
+const CHUNK_FRAMES: usize = 4096;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportOptions {
+    // From the output's extension when unset
+    pub format: Option<OutputFormat>,
+    pub bitrate: Option<BitrateMode>,
+    pub eq: Option<EqCurve>,
+    // Undo the pre-emphasis the recording was made with; follows its
+    // metadata when unset
+    pub de_emphasis: Option<bool>,
+}
+
+// Export a WAV recording to `output`, applying the optional EQ on the way
+#[tauri::command]
+pub async fn export_recording(input: String, output: String, options: Option<ExportOptions>) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let input = Path::new(&input);
+    let output = PathBuf::from(output);
+    let format = convert::resolve_output(input, &output, options.format)?;
+    if let Some(mode) = options.bitrate {
+        mode.validate(format)?;
+    }
+    let emphasis = match options.de_emphasis {
+        Some(false) => None,
+        Some(true) => Some(
+            metadata::read(input)?
+                .emphasis
+                .ok_or("Recording was not made with pre-emphasis")?,
+        ),
+        None => metadata::emphasis(input),
+    };
+
+    // Don't leave a truncated file behind
+    process(input, &output, format, options.bitrate, options.eq.as_ref(), emphasis).inspect_err(|_| {
+        let _ = std::fs::remove_file(&output);
+    })?;
+
+    Ok(output.to_string_lossy().to_string())
+}
+
+// Stream `input` through de-emphasis and the EQ into `format`
+fn process(
+    input: &Path,
+    output: &Path,
+    format: OutputFormat,
+    bitrate: Option<BitrateMode>,
+    eq: Option<&EqCurve>,
+    emphasis: Option<f32>,
+) -> Result<(), String> {
+    let mut stream = WavStream::open(input)?;
+    let source = stream.spec();
+    let mut filters = match eq {
+        Some(curve) => Some(curve.build(source.sample_rate, source.channels as usize)?),
+        None => None,
+    };
+    let mut de_emphasis = emphasis.map(|coefficient| Emphasis::de(coefficient, source.channels as usize));
+
+    let mut sink = encode::create_sink(output, format, source, bitrate)?;
+    let mut buffer = Vec::with_capacity(CHUNK_FRAMES * source.channels as usize);
+    while stream.read_chunk(CHUNK_FRAMES, &mut buffer)? > 0 {
+        if let Some(de_emphasis) = de_emphasis.as_mut() {
+            de_emphasis.process_interleaved(&mut buffer);
+        }
+        if let Some(filters) = filters.as_mut() {
+            filters.process_interleaved(&mut buffer);
+        }
+        sink.write(&buffer)?;
+    }
+    sink.finalize()
+}
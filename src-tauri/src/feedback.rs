@@ -0,0 +1,120 @@
+use std::f32::consts::PI;
+use std::thread;
+use std::time::Duration;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::playback;
+use crate::RecordingState;
+
+const AMPLITUDE: f32 = 0.25;
+const RAMP_MS: f32 = 5.0;
+// Extra wait after the tone so device buffers drain before capture starts
+const DRAIN_MS: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackTone {
+    Start,
+    Stop,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedbackToneInfo {
+    pub kind: FeedbackTone,
+    pub duration_ms: u64,
+}
+
+impl FeedbackTone {
+    pub const ALL: [FeedbackTone; 3] = [FeedbackTone::Start, FeedbackTone::Stop, FeedbackTone::Error];
+
+    // Notes as (frequency Hz, length ms); a frequency of 0 is a rest
+    fn notes(self) -> &'static [(f32, u32)] {
+        match self {
+            FeedbackTone::Start => &[(660.0, 80), (880.0, 120)],
+            FeedbackTone::Stop => &[(880.0, 80), (660.0, 120)],
+            FeedbackTone::Error => &[(220.0, 120), (0.0, 60), (220.0, 120)],
+        }
+    }
+
+    pub fn duration_ms(self) -> u64 {
+        self.notes().iter().map(|&(_, ms)| ms as u64).sum()
+    }
+
+    // Mono samples at `sample_rate`, each note ramped in and out to avoid clicks
+    fn render(self, sample_rate: u32) -> Vec<f32> {
+        let rate = sample_rate as f32;
+        let ramp = (RAMP_MS / 1000.0 * rate).max(1.0);
+        let mut samples = Vec::new();
+        for &(frequency, ms) in self.notes() {
+            let len = (ms as f32 / 1000.0 * rate) as usize;
+            samples.extend((0..len).map(|n| {
+                if frequency == 0.0 {
+                    return 0.0;
+                }
+                let envelope = (n as f32 / ramp).min((len - n) as f32 / ramp).min(1.0);
+                AMPLITUDE * envelope * (2.0 * PI * frequency * n as f32 / rate).sin()
+            }));
+        }
+        samples
+    }
+}
+
+// Play `tone` on the default output device and return once it has finished
+pub fn play_blocking(tone: FeedbackTone) -> Result<(), String> {
+    let device = playback::find_output_device(None)?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get default output config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    let channels = config.channels as usize;
+    let samples = tone.render(config.sample_rate.0);
+
+    let mut position = 0;
+    let stream = playback::build_f32_output_stream(&device, &config, sample_format, move |data| {
+        for frame in data.chunks_mut(channels) {
+            let sample = samples.get(position).copied().unwrap_or(0.0);
+            frame.fill(sample);
+            position += 1;
+        }
+    })?;
+    stream.play().map_err(|e| format!("Failed to start output stream: {}", e))?;
+    thread::sleep(Duration::from_millis(tone.duration_ms() + DRAIN_MS));
+    Ok(())
+}
+
+// Lifecycle feedback is best-effort and never holds up the caller
+pub fn play_in_background(tone: FeedbackTone) {
+    thread::spawn(move || {
+        if let Err(e) = play_blocking(tone) {
+            eprintln!("Feedback tone error: {}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn list_feedback_tones() -> Vec<FeedbackToneInfo> {
+    FeedbackTone::ALL
+        .iter()
+        .map(|&kind| FeedbackToneInfo {
+            kind,
+            duration_ms: kind.duration_ms(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn play_feedback_tone(kind: FeedbackTone) -> Result<(), String> {
+    play_blocking(kind)
+}
+
+// Toggle the tones played when recording starts, stops or fails
+#[tauri::command]
+pub async fn set_feedback_enabled(state: State<'_, RecordingState>, enabled: bool) -> Result<(), String> {
+    *state.feedback_enabled.lock().map_err(|e| e.to_string())? = enabled;
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::dsp::{self, Biquad, FilterChain, NoiseGate};
+use crate::levels;
+use crate::RecordingState;
+
+// How long a stage takes to fade in or out when toggled, so switching it
+// mid-take doesn't click
+const RAMP_MS: u64 = 20;
+const MAX_GAIN_DB: f32 = 24.0;
+const MIN_HIGHPASS_HZ: f32 = 20.0;
+const MAX_HIGHPASS_HZ: f32 = 1_000.0;
+const HIGHPASS_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+const SOFT_CLIP_KNEE: f32 = 0.5;
+
+// Processing applied to the input before it is written, in this order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FilterStage {
+    Gain,
+    Highpass,
+    NoiseGate,
+    SoftClip,
+}
+
+impl FilterStage {
+    const ALL: [FilterStage; 4] = [
+        FilterStage::Gain,
+        FilterStage::Highpass,
+        FilterStage::NoiseGate,
+        FilterStage::SoftClip,
+    ];
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FilterSettings {
+    pub gain_db: f32,
+    pub highpass_hz: f32,
+    pub gate_threshold_dbfs: f32,
+    // Stages on when recording starts; the rest can be switched on mid-take
+    pub enabled: Vec<FilterStage>,
+}
+
+impl Default for FilterSettings {
+    fn default() -> Self {
+        Self {
+            gain_db: 6.0,
+            highpass_hz: 80.0,
+            gate_threshold_dbfs: -50.0,
+            enabled: Vec::new(),
+        }
+    }
+}
+
+impl FilterSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-MAX_GAIN_DB..=MAX_GAIN_DB).contains(&self.gain_db) {
+            return Err(format!("Filter gain must be between -{0} and {0} dB", MAX_GAIN_DB));
+        }
+        if !(MIN_HIGHPASS_HZ..=MAX_HIGHPASS_HZ).contains(&self.highpass_hz) {
+            return Err(format!(
+                "Highpass cutoff must be between {} and {} Hz",
+                MIN_HIGHPASS_HZ, MAX_HIGHPASS_HZ
+            ));
+        }
+        if !(levels::MIN_DBFS..=0.0).contains(&self.gate_threshold_dbfs) {
+            return Err(format!("Gate threshold must be between {} and 0 dBFS", levels::MIN_DBFS));
+        }
+        Ok(())
+    }
+}
+
+// Which stages are on, shared with the input callback
+#[derive(Debug, Default)]
+pub struct FilterToggles {
+    enabled: [AtomicBool; 4],
+}
+
+impl FilterToggles {
+    pub fn set(&self, stage: FilterStage, enabled: bool) {
+        self.enabled[stage as usize].store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn is_enabled(&self, stage: FilterStage) -> bool {
+        self.enabled[stage as usize].load(Ordering::Relaxed)
+    }
+
+    // Turn on exactly the stages in `enabled`
+    pub fn reset(&self, enabled: &[FilterStage]) {
+        for stage in FilterStage::ALL {
+            self.set(stage, enabled.contains(&stage));
+        }
+    }
+
+    pub fn state(&self) -> FilterChainState {
+        FilterChainState {
+            gain: self.is_enabled(FilterStage::Gain),
+            highpass: self.is_enabled(FilterStage::Highpass),
+            noise_gate: self.is_enabled(FilterStage::NoiseGate),
+            soft_clip: self.is_enabled(FilterStage::SoftClip),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterChainState {
+    pub gain: bool,
+    pub highpass: bool,
+    pub noise_gate: bool,
+    pub soft_clip: bool,
+}
+
+// Switch one stage on or off, taking effect in the running recording within
+// a few milliseconds. Also emits the chain as "filter-chain".
+#[tauri::command]
+pub async fn set_filter_enabled(
+    app_handle: AppHandle,
+    state: State<'_, RecordingState>,
+    stage: FilterStage,
+    enabled: bool,
+) -> Result<FilterChainState, String> {
+    state.filters.set(stage, enabled);
+    let chain = state.filters.state();
+    let _ = app_handle.emit("filter-chain", chain.clone());
+    Ok(chain)
+}
+
+#[tauri::command]
+pub async fn get_filter_chain(state: State<'_, RecordingState>) -> Result<FilterChainState, String> {
+    Ok(state.filters.state())
+}
+
+// The stages as run in the input callback. Each stage always runs so its
+// state is warm, and its output is crossfaded with its input by a mix that
+// eases towards the toggle.
+pub struct LiveFilters {
+    toggles: Arc<FilterToggles>,
+    channels: usize,
+    mix: [f32; 4],
+    ramp_step: f32,
+    gain: f32,
+    highpass: FilterChain,
+    gate: NoiseGate,
+    wet: Vec<f32>,
+}
+
+impl LiveFilters {
+    pub fn new(settings: &FilterSettings, toggles: Arc<FilterToggles>, sample_rate: u32, channels: usize) -> Self {
+        let mix = FilterStage::ALL.map(|stage| if toggles.is_enabled(stage) { 1.0 } else { 0.0 });
+        Self {
+            toggles,
+            channels,
+            mix,
+            ramp_step: 1000.0 / (RAMP_MS * sample_rate as u64) as f32,
+            gain: 10f32.powf(settings.gain_db / 20.0),
+            highpass: FilterChain::new(&[Biquad::highpass(sample_rate, settings.highpass_hz, HIGHPASS_Q)], channels),
+            gate: NoiseGate::new(sample_rate, channels, settings.gate_threshold_dbfs),
+            wet: Vec::new(),
+        }
+    }
+
+    pub fn process_interleaved(&mut self, data: &mut [f32]) {
+        if self.channels == 0 {
+            return;
+        }
+        for stage in FilterStage::ALL {
+            self.wet.clear();
+            self.wet.extend_from_slice(data);
+            match stage {
+                FilterStage::Gain => self.wet.iter_mut().for_each(|sample| *sample *= self.gain),
+                FilterStage::Highpass => self.highpass.process_interleaved(&mut self.wet),
+                FilterStage::NoiseGate => self.gate.process_interleaved(&mut self.wet),
+                FilterStage::SoftClip => self
+                    .wet
+                    .iter_mut()
+                    .for_each(|sample| *sample = dsp::soft_clip(*sample, SOFT_CLIP_KNEE)),
+            }
+
+            let target = if self.toggles.is_enabled(stage) { 1.0 } else { 0.0 };
+            let mix = &mut self.mix[stage as usize];
+            if *mix == target {
+                if target == 1.0 {
+                    data.copy_from_slice(&self.wet);
+                }
+                continue;
+            }
+            for (frame, wet) in data
+                .chunks_exact_mut(self.channels)
+                .zip(self.wet.chunks_exact(self.channels))
+            {
+                *mix = if target > *mix {
+                    (*mix + self.ramp_step).min(target)
+                } else {
+                    (*mix - self.ramp_step).max(target)
+                };
+                for (sample, wet) in frame.iter_mut().zip(wet) {
+                    *sample += (wet - *sample) * *mix;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::path::Path;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+
+use crate::decode;
+use crate::dsp::{Biquad, FilterChain, LinearResampler};
+
+// Everything is analyzed as mono at this rate, so fingerprints of the same
+// audio compare equal whatever it was recorded at
+const ANALYSIS_RATE: u32 = 11_025;
+const FRAME: usize = 2048;
+// About 93 ms per sub-fingerprint
+const HOP: usize = 1024;
+// Anti-alias filter ahead of the resampler; nothing above it is analyzed
+const LOWPASS_HZ: f32 = 4_000.0;
+const BUTTERWORTH_Q: [f32; 2] = [0.5412, 1.3066];
+// Overlap needed before an alignment is scored, in sub-fingerprints, and as a
+// share of the shorter one; short overlaps match by chance too easily
+const MIN_OVERLAP: usize = 20;
+const MIN_OVERLAP_SHARE: f64 = 0.5;
+// Below this many comparisons every alignment is tried, not just the likely ones
+const BRUTE_FORCE_LIMIT: usize = 50_000_000;
+const VOTED_OFFSETS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FingerprintKind {
+    // Energy differences across 33 bands, after Haitsma and Kalker
+    Spectral,
+    // Pitch-class energy, which survives a change of mic or room better;
+    // needs the `chromaprint` feature
+    Chroma,
+}
+
+impl FingerprintKind {
+    // Bit error rate under which two recordings are taken to be the same
+    // audio. Unrelated audio sits nearer 0.5, less so for chroma, whose bits
+    // stay put through sustained notes.
+    fn duplicate_ber(self) -> f64 {
+        match self {
+            FingerprintKind::Spectral => 0.35,
+            FingerprintKind::Chroma => 0.2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fingerprint {
+    pub kind: FingerprintKind,
+    pub hop_secs: f64,
+    pub duration_secs: f64,
+    // One 32-bit sub-fingerprint per hop
+    pub items: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FingerprintMatch {
+    // 1 for identical audio, 0 for no more alike than chance
+    pub similarity: f64,
+    pub bit_error_rate: f64,
+    // Where `b` starts within `a` at the best alignment; negative when `b`
+    // starts first
+    pub offset_secs: f64,
+    pub overlap_secs: f64,
+    pub likely_duplicate: bool,
+}
+
+// Perceptual fingerprint of a recording, decoded in chunks so long files
+// never sit in memory. Spectral unless `kind` says otherwise.
+#[tauri::command]
+pub async fn audio_fingerprint(path: String, kind: Option<FingerprintKind>) -> Result<Fingerprint, String> {
+    let kind = kind.unwrap_or(FingerprintKind::Spectral);
+    let mut analyzer = Analyzer::new(kind)?;
+    let mut source = decode::open(Path::new(&path))?;
+    let info = source.info();
+    let channels = info.channels as usize;
+    let mut lowpass = (info.sample_rate > ANALYSIS_RATE)
+        .then(|| FilterChain::new(&BUTTERWORTH_Q.map(|q| Biquad::lowpass(info.sample_rate, LOWPASS_HZ, q)), 1));
+    let mut resampler = LinearResampler::new(info.sample_rate, ANALYSIS_RATE, 1);
+
+    let mut buffer = Vec::new();
+    let mut mono = Vec::new();
+    let mut resampled = Vec::new();
+    let mut pending: Vec<f32> = Vec::new();
+    let mut frames = 0u64;
+    loop {
+        let read = source.read_chunk(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        frames += read as u64;
+        mono.clear();
+        mono.extend(buffer.chunks_exact(channels).map(|frame| frame.iter().sum::<f32>() / channels as f32));
+        if let Some(lowpass) = lowpass.as_mut() {
+            lowpass.process_interleaved(&mut mono);
+        }
+        resampler.process_interleaved(&mono, &mut resampled);
+        pending.extend_from_slice(&resampled);
+        let mut start = 0;
+        while start + FRAME <= pending.len() {
+            analyzer.push(&pending[start..start + FRAME]);
+            start += HOP;
+        }
+        pending.drain(..start);
+    }
+    if analyzer.items.is_empty() {
+        return Err("Recording is too short to fingerprint".to_string());
+    }
+    Ok(Fingerprint {
+        kind,
+        hop_secs: HOP as f64 / ANALYSIS_RATE as f64,
+        duration_secs: frames as f64 / info.sample_rate as f64,
+        items: analyzer.items,
+    })
+}
+
+// How alike two fingerprints are at the alignment where they match best
+#[tauri::command]
+pub async fn compare_fingerprints(a: Fingerprint, b: Fingerprint) -> Result<FingerprintMatch, String> {
+    if a.kind != b.kind {
+        return Err("Fingerprints were made with different methods".to_string());
+    }
+    let shorter = a.items.len().min(b.items.len());
+    let min_overlap = MIN_OVERLAP.max((shorter as f64 * MIN_OVERLAP_SHARE) as usize).min(shorter);
+    if min_overlap == 0 {
+        return Err("Fingerprint is empty".to_string());
+    }
+    // Offsets of b's start within a that leave enough overlap
+    let first = -(b.items.len() as i64 - min_overlap as i64);
+    let last = a.items.len() as i64 - min_overlap as i64;
+    let offsets: Vec<i64> = if a.items.len() * b.items.len() <= BRUTE_FORCE_LIMIT {
+        (first..=last).collect()
+    } else {
+        likely_offsets(&a.items, &b.items)
+            .into_iter()
+            .filter(|offset| (first..=last).contains(offset))
+            .collect()
+    };
+
+    let mut best: Option<(f64, i64, usize)> = None;
+    for offset in offsets {
+        let (errors, overlap) = bit_errors(&a.items, &b.items, offset);
+        let ber = errors as f64 / (overlap * 32) as f64;
+        if best.is_none_or(|(best_ber, _, _)| ber < best_ber) {
+            best = Some((ber, offset, overlap));
+        }
+    }
+    let (bit_error_rate, offset, overlap) = best.ok_or("Fingerprints don't overlap")?;
+    Ok(FingerprintMatch {
+        similarity: (1.0 - 2.0 * bit_error_rate).clamp(0.0, 1.0),
+        bit_error_rate,
+        offset_secs: offset as f64 * a.hop_secs,
+        overlap_secs: overlap as f64 * a.hop_secs,
+        likely_duplicate: bit_error_rate < a.kind.duplicate_ber(),
+    })
+}
+
+// Differing bits and the number of items compared with b shifted by `offset`
+fn bit_errors(a: &[u32], b: &[u32], offset: i64) -> (u64, usize) {
+    let a_start = offset.max(0) as usize;
+    let b_start = (-offset).max(0) as usize;
+    let overlap = (a.len() - a_start).min(b.len() - b_start);
+    let errors = a[a_start..a_start + overlap]
+        .iter()
+        .zip(&b[b_start..b_start + overlap])
+        .map(|(x, y)| (x ^ y).count_ones() as u64)
+        .sum();
+    (errors, overlap)
+}
+
+// Offsets voted for by sub-fingerprints that match exactly, plus no offset
+fn likely_offsets(a: &[u32], b: &[u32]) -> Vec<i64> {
+    let mut positions: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (index, item) in a.iter().enumerate() {
+        positions.entry(*item).or_default().push(index);
+    }
+    let mut votes: HashMap<i64, u32> = HashMap::new();
+    for (index, item) in b.iter().enumerate() {
+        for position in positions.get(item).into_iter().flatten() {
+            *votes.entry(*position as i64 - index as i64).or_default() += 1;
+        }
+    }
+    let mut voted: Vec<(i64, u32)> = votes.into_iter().collect();
+    voted.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let mut offsets: Vec<i64> = voted.into_iter().take(VOTED_OFFSETS).map(|(offset, _)| offset).collect();
+    if !offsets.contains(&0) {
+        offsets.push(0);
+    }
+    offsets
+}
+
+// Per-frame features from a power spectrum, and the sub-fingerprint of a
+// frame's features against the previous frame's
+type FeaturesFn = fn(&[f32]) -> Vec<f32>;
+type BitsFn = fn(&[f32], &[f32]) -> u32;
+
+// Turns full analysis frames into sub-fingerprints
+struct Analyzer {
+    features: FeaturesFn,
+    bits: BitsFn,
+    fft: std::sync::Arc<dyn rustfft::Fft<f32>>,
+    window: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    power: Vec<f32>,
+    previous: Option<Vec<f32>>,
+    items: Vec<u32>,
+}
+
+impl Analyzer {
+    fn new(kind: FingerprintKind) -> Result<Self, String> {
+        let (features, bits): (FeaturesFn, BitsFn) = match kind {
+            FingerprintKind::Spectral => (spectral::features, spectral::bits),
+            #[cfg(feature = "chromaprint")]
+            FingerprintKind::Chroma => (chroma::features, chroma::bits),
+            #[cfg(not(feature = "chromaprint"))]
+            FingerprintKind::Chroma => return Err("Chroma fingerprints are not compiled into this build".to_string()),
+        };
+        let window = (0..FRAME)
+            .map(|n| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * n as f32 / FRAME as f32).cos())
+            .collect();
+        Ok(Self {
+            features,
+            bits,
+            fft: FftPlanner::new().plan_fft_forward(FRAME),
+            window,
+            spectrum: vec![Complex::default(); FRAME],
+            power: vec![0.0; FRAME / 2],
+            previous: None,
+            items: Vec::new(),
+        })
+    }
+
+    fn push(&mut self, frame: &[f32]) {
+        for ((bin, sample), weight) in self.spectrum.iter_mut().zip(frame).zip(&self.window) {
+            *bin = Complex::new(sample * weight, 0.0);
+        }
+        self.fft.process(&mut self.spectrum);
+        for (power, bin) in self.power.iter_mut().zip(&self.spectrum) {
+            *power = bin.norm_sqr();
+        }
+        let features = (self.features)(&self.power);
+        // Every bit compares against the frame before, so the first has none
+        if let Some(previous) = self.previous.as_ref() {
+            self.items.push((self.bits)(&features, previous));
+        }
+        self.previous = Some(features);
+    }
+}
+
+fn bin_hz(bin: usize) -> f32 {
+    bin as f32 * ANALYSIS_RATE as f32 / FRAME as f32
+}
+
+mod spectral {
+    use super::bin_hz;
+
+    const BANDS: usize = 33;
+    const MIN_HZ: f32 = 300.0;
+    const MAX_HZ: f32 = 2_000.0;
+
+    // Energy in log-spaced bands over MIN_HZ..MAX_HZ
+    pub fn features(power: &[f32]) -> Vec<f32> {
+        let mut bands = vec![0.0f32; BANDS];
+        let span = (MAX_HZ / MIN_HZ).ln();
+        for (bin, power) in power.iter().enumerate() {
+            let hz = bin_hz(bin);
+            if (MIN_HZ..MAX_HZ).contains(&hz) {
+                let band = ((hz / MIN_HZ).ln() / span * BANDS as f32) as usize;
+                bands[band.min(BANDS - 1)] += power;
+            }
+        }
+        bands
+    }
+
+    // Bit m is set when the energy step from band m to m + 1 grew since the
+    // previous frame
+    pub fn bits(current: &[f32], previous: &[f32]) -> u32 {
+        (0..32).fold(0u32, |bits, m| {
+            let delta = (current[m] - current[m + 1]) - (previous[m] - previous[m + 1]);
+            bits | (((delta > 0.0) as u32) << m)
+        })
+    }
+}
+
+#[cfg(feature = "chromaprint")]
+mod chroma {
+    use super::bin_hz;
+
+    const MIN_HZ: f32 = 28.0;
+    const MAX_HZ: f32 = 3_520.0;
+
+    // Energy per pitch class over MIN_HZ..MAX_HZ, summing to 1
+    pub fn features(power: &[f32]) -> Vec<f32> {
+        let mut chroma = vec![0.0f32; 12];
+        for (bin, power) in power.iter().enumerate() {
+            let hz = bin_hz(bin);
+            if (MIN_HZ..MAX_HZ).contains(&hz) {
+                let note = (12.0 * (hz / 440.0).log2()).round() as i32 + 69;
+                chroma[note.rem_euclid(12) as usize] += power;
+            }
+        }
+        let total: f32 = chroma.iter().sum();
+        if total > 0.0 {
+            chroma.iter_mut().for_each(|value| *value /= total);
+        }
+        chroma
+    }
+
+    // 12 bits comparing neighbouring pitch classes, 12 comparing each class
+    // with the previous frame and 8 comparing pairs a tritone apart
+    pub fn bits(current: &[f32], previous: &[f32]) -> u32 {
+        let mut bits = 0u32;
+        for i in 0..12 {
+            bits |= ((current[i] > current[(i + 1) % 12]) as u32) << i;
+            bits |= ((current[i] > previous[i]) as u32) << (12 + i);
+        }
+        for i in 0..8 {
+            let pair = current[i] + current[(i + 1) % 12];
+            let opposite = current[(i + 6) % 12] + current[(i + 7) % 12];
+            bits |= ((pair > opposite) as u32) << (24 + i);
+        }
+        bits
+    }
+}
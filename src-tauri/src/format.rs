@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+
+// Bytes per second of the 16-bit PCM the recorder captures into
+pub fn pcm_bytes_per_second(sample_rate: u32, channels: u16) -> u64 {
+    sample_rate as u64 * channels as u64 * 2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Wav,
+    Aiff,
+    Mp3,
+}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Wav => "wav",
+            OutputFormat::Aiff => "aiff",
+            OutputFormat::Mp3 => "mp3",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "wav" => Some(OutputFormat::Wav),
+            "aiff" | "aif" => Some(OutputFormat::Aiff),
+            "mp3" => Some(OutputFormat::Mp3),
+            _ => None,
+        }
+    }
+
+    // Uncompressed formats are written straight from PCM frames
+    pub fn is_pcm(self) -> bool {
+        matches!(self, OutputFormat::Wav | OutputFormat::Aiff)
+    }
+
+    // Whether an encoder for this format was compiled into the build
+    pub fn is_available(self) -> bool {
+        match self {
+            OutputFormat::Wav | OutputFormat::Aiff => true,
+            OutputFormat::Mp3 => cfg!(feature = "mp3"),
+        }
+    }
+
+    pub fn ensure_available(self) -> Result<(), String> {
+        if self.is_available() {
+            Ok(())
+        } else {
+            Err(format!("{} support is not compiled into this build", self.extension().to_uppercase()))
+        }
+    }
+}
+
+// Bitrates an MP3 frame can be coded at
+const MP3_CBR_KBPS: [u32; 16] = [8, 16, 24, 32, 40, 48, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+const MAX_VBR_QUALITY: u8 = 9;
+// Typical average rate of each VBR quality, for size estimates
+const VBR_APPROX_KBPS: [u32; 10] = [245, 225, 190, 175, 165, 130, 115, 100, 85, 65];
+
+// How a compressed format spends its bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BitrateMode {
+    // Every frame at this rate
+    Cbr(u32),
+    // Rate follows the content; quality 0 is the best, 9 the smallest
+    Vbr { quality: u8 },
+    // Varies frame to frame around this average
+    Abr(u32),
+}
+
+impl BitrateMode {
+    // Check the mode against what `format`'s encoder accepts
+    pub fn validate(self, format: OutputFormat) -> Result<(), String> {
+        if format.is_pcm() {
+            return Err(format!("{} is uncompressed and takes no bitrate", format.extension().to_uppercase()));
+        }
+        match self {
+            BitrateMode::Cbr(kbps) if !MP3_CBR_KBPS.contains(&kbps) => Err(format!(
+                "Unsupported MP3 bitrate: {} kbps (use one of {:?})",
+                kbps, MP3_CBR_KBPS
+            )),
+            BitrateMode::Vbr { quality } if quality > MAX_VBR_QUALITY => Err(format!(
+                "VBR quality must be between 0 (best) and {}",
+                MAX_VBR_QUALITY
+            )),
+            BitrateMode::Abr(kbps) if !(MP3_CBR_KBPS[0]..=MP3_CBR_KBPS[15]).contains(&kbps) => Err(format!(
+                "ABR bitrate must be between {} and {} kbps",
+                MP3_CBR_KBPS[0], MP3_CBR_KBPS[15]
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    // Average rate the mode ends up at, roughly for VBR
+    pub fn approx_kbps(self) -> u32 {
+        match self {
+            BitrateMode::Cbr(kbps) | BitrateMode::Abr(kbps) => kbps,
+            BitrateMode::Vbr { quality } => VBR_APPROX_KBPS[quality.min(MAX_VBR_QUALITY) as usize],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityPreset {
+    Lossless,
+    High,
+    Standard,
+    Compact,
+}
+
+impl QualityPreset {
+    // Ordered from largest to smallest output
+    pub const ALL: [QualityPreset; 4] = [
+        QualityPreset::Lossless,
+        QualityPreset::High,
+        QualityPreset::Standard,
+        QualityPreset::Compact,
+    ];
+
+    pub fn format(self) -> OutputFormat {
+        match self {
+            QualityPreset::Lossless => OutputFormat::Wav,
+            _ => OutputFormat::Mp3,
+        }
+    }
+
+    pub fn bitrate_kbps(self) -> Option<u32> {
+        match self {
+            QualityPreset::Lossless => None,
+            QualityPreset::High => Some(256),
+            QualityPreset::Standard => Some(160),
+            QualityPreset::Compact => Some(96),
+        }
+    }
+
+    // Approximate size of the encoded output, used for disk-space planning
+    pub fn bytes_per_second(self, sample_rate: u32, channels: u16) -> u64 {
+        match self.bitrate_kbps() {
+            Some(kbps) => kbps as u64 * 1000 / 8,
+            None => pcm_bytes_per_second(sample_rate, channels),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoFormatPolicy {
+    // Hours of recording the free space must hold for a preset to be picked
+    pub min_hours: f64,
+}
+
+impl Default for AutoFormatPolicy {
+    fn default() -> Self {
+        Self { min_hours: 2.0 }
+    }
+}
+
+// Highest free-space floor a low space policy takes: 16 TB
+const MAX_MIN_FREE_MB: u64 = 16 * 1024 * 1024;
+
+// Switch to smaller output partway through a recording once free space runs low
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LowSpacePolicy {
+    pub min_free_mb: u64,
+}
+
+impl LowSpacePolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_free_mb > MAX_MIN_FREE_MB {
+            return Err(format!("Low space floor can be at most {} MB", MAX_MIN_FREE_MB));
+        }
+        Ok(())
+    }
+}
+
+impl Default for LowSpacePolicy {
+    fn default() -> Self {
+        Self { min_free_mb: 2_048 }
+    }
+}
+
+// The largest available preset that is smaller than output currently taking
+// `bytes_per_second`, if any
+pub fn smaller_preset(bytes_per_second: u64, sample_rate: u32, channels: u16) -> Option<QualityPreset> {
+    QualityPreset::ALL.iter().copied().find(|preset| {
+        preset.format().is_available()
+            // MP3 can't hold more than stereo
+            && (preset.format().is_pcm() || channels <= 2)
+            && preset.bytes_per_second(sample_rate, channels) < bytes_per_second
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatChoice {
+    pub preset: QualityPreset,
+    pub format: OutputFormat,
+    pub free_bytes: u64,
+    pub hours_available: f64,
+    pub reason: String,
+}
+
+// Pick the highest-quality available preset that still leaves room for
+// `policy.min_hours` of recording, falling back to the most compact one
+pub fn choose_format(free_bytes: u64, sample_rate: u32, channels: u16, policy: &AutoFormatPolicy) -> FormatChoice {
+    let hours_for = |preset: QualityPreset| {
+        free_bytes as f64 / preset.bytes_per_second(sample_rate, channels) as f64 / 3600.0
+    };
+    let available: Vec<QualityPreset> = QualityPreset::ALL
+        .iter()
+        .copied()
+        // MP3 can't hold more than stereo
+        .filter(|preset| preset.format().is_available() && (preset.format().is_pcm() || channels <= 2))
+        .collect();
+
+    if let Some(&preset) = available.iter().find(|&&preset| hours_for(preset) >= policy.min_hours) {
+        let hours_available = hours_for(preset);
+        let reason = if preset == QualityPreset::Lossless {
+            format!("{:.1} h of lossless audio fits in free space", hours_available)
+        } else {
+            format!(
+                "Only {:.1} h of lossless audio fits in free space, using {:?} ({:.1} h)",
+                hours_for(QualityPreset::Lossless),
+                preset,
+                hours_available
+            )
+        };
+        return FormatChoice {
+            preset,
+            format: preset.format(),
+            free_bytes,
+            hours_available,
+            reason,
+        };
+    }
+
+    let preset = available.last().copied().unwrap_or(QualityPreset::Lossless);
+    let hours_available = hours_for(preset);
+    let reason = if available.len() == 1 && channels > 2 {
+        format!(
+            "Free space is low ({:.1} h) but MP3 can't hold {} channels",
+            hours_available, channels
+        )
+    } else if available.len() == 1 {
+        format!(
+            "Free space is low ({:.1} h) but no compressed format is compiled into this build",
+            hours_available
+        )
+    } else {
+        format!(
+            "Free space is low, using the most compact preset ({:.1} h fits)",
+            hours_available
+        )
+    };
+    FormatChoice {
+        preset,
+        format: preset.format(),
+        free_bytes,
+        hours_available,
+        reason,
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+
+use crate::levels::ChannelLevels;
+
+const MIN_INTERVAL_MS: u64 = 10;
+// Upper bound on buffered PCM if the frontend falls behind
+const MAX_BUFFERED_SECS: usize = 2;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum AudioFrame {
+    Levels {
+        peak_dbfs: Vec<f32>,
+        rms_dbfs: Vec<f32>,
+        frames: u64,
+    },
+    Pcm {
+        channels: u16,
+        sample_rate: u32,
+        samples: Vec<i16>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FrameStreamOptions {
+    pub interval_ms: u64,
+    // Also send the raw PCM, not just levels
+    pub pcm: bool,
+}
+
+impl Default for FrameStreamOptions {
+    fn default() -> Self {
+        Self {
+            interval_ms: 50,
+            pcm: false,
+        }
+    }
+}
+
+pub struct FrameStream {
+    pub channel: Channel<AudioFrame>,
+    pub options: FrameStreamOptions,
+}
+
+// Accumulates what the audio callback saw since the last frame was sent
+pub struct FrameTap {
+    channels: u16,
+    sample_rate: u32,
+    keep_pcm: bool,
+    levels: ChannelLevels,
+    pcm: Vec<i16>,
+}
+
+impl FrameTap {
+    pub fn new(channels: u16, sample_rate: u32, keep_pcm: bool) -> Self {
+        Self {
+            channels,
+            sample_rate,
+            keep_pcm,
+            levels: ChannelLevels::new(channels as usize),
+            pcm: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, data: &[f32]) {
+        self.levels.push_interleaved(data);
+        if self.keep_pcm {
+            let limit = self.sample_rate as usize * self.channels as usize * MAX_BUFFERED_SECS;
+            if self.pcm.len() + data.len() > limit {
+                self.pcm.clear();
+            }
+            self.pcm
+                .extend(data.iter().map(|&sample| (sample * i16::MAX as f32) as i16));
+        }
+    }
+
+    fn take_frames(&mut self) -> Vec<AudioFrame> {
+        let levels = std::mem::replace(&mut self.levels, ChannelLevels::new(self.channels as usize));
+        let mut frames = vec![AudioFrame::Levels {
+            peak_dbfs: levels.peak_dbfs(),
+            rms_dbfs: levels.rms_dbfs(),
+            frames: levels.frames(),
+        }];
+        if self.keep_pcm && !self.pcm.is_empty() {
+            frames.push(AudioFrame::Pcm {
+                channels: self.channels,
+                sample_rate: self.sample_rate,
+                samples: std::mem::take(&mut self.pcm),
+            });
+        }
+        frames
+    }
+}
+
+// Send accumulated frames on a fixed interval until recording stops or the
+// frontend drops the channel. While the window is hidden frames are still
+// drained, just not sent.
+pub fn spawn_sender(
+    stream: FrameStream,
+    tap: Arc<Mutex<FrameTap>>,
+    is_recording: Arc<Mutex<bool>>,
+    window_visible: Arc<Mutex<bool>>,
+) -> thread::JoinHandle<()> {
+    let interval = Duration::from_millis(stream.options.interval_ms.max(MIN_INTERVAL_MS));
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        if !is_recording.lock().map(|recording| *recording).unwrap_or(false) {
+            break;
+        }
+        let frames = match tap.lock() {
+            Ok(mut tap) => tap.take_frames(),
+            Err(_) => break,
+        };
+        if !window_visible.lock().map(|visible| *visible).unwrap_or(true) {
+            continue;
+        }
+        if frames.into_iter().any(|frame| stream.channel.send(frame).is_err()) {
+            break;
+        }
+    })
+}
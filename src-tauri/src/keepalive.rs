@@ -0,0 +1,137 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use cpal::traits::{DeviceTrait, StreamTrait};
+
+use crate::capture;
+
+const IDLE_CHECK_MS: u64 = 1_000;
+
+// What the audio callback hands samples to; None while nobody is recording
+pub type InputConsumer = Box<dyn FnMut(&[f32]) + Send>;
+type ConsumerSlot = Arc<Mutex<Option<InputConsumer>>>;
+
+// The device and config a kept stream was opened with; a recording that
+// wants anything else gets a fresh stream
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamKey {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub sample_format: cpal::SampleFormat,
+    pub buffer_frames: Option<u32>,
+}
+
+// An input stream left open between recordings so the next one starts
+// without reopening the device. The stream lives on its own thread (cpal
+// streams can't move between threads on every host) and discards samples
+// while no consumer is installed.
+pub struct KeptInput {
+    key: StreamKey,
+    consumer: ConsumerSlot,
+    last_used: Arc<Mutex<Instant>>,
+    release: Sender<()>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl KeptInput {
+    pub fn open(
+        device: cpal::Device,
+        config: cpal::StreamConfig,
+        sample_format: cpal::SampleFormat,
+        idle_timeout: Duration,
+    ) -> Result<Self, String> {
+        let key = StreamKey {
+            device: device.name().unwrap_or_else(|_| "Unknown device".to_string()),
+            sample_rate: config.sample_rate.0,
+            channels: config.channels,
+            sample_format,
+            buffer_frames: match config.buffer_size {
+                cpal::BufferSize::Fixed(frames) => Some(frames),
+                cpal::BufferSize::Default => None,
+            },
+        };
+        let consumer: ConsumerSlot = Arc::new(Mutex::new(None));
+        let last_used = Arc::new(Mutex::new(Instant::now()));
+        let (release, released) = mpsc::channel();
+        let (opened_tx, opened) = mpsc::channel();
+
+        let consumer_ref = consumer.clone();
+        let idle_consumer = consumer.clone();
+        let last_used_ref = last_used.clone();
+        let thread = thread::spawn(move || {
+            let stream = capture::build_f32_input_stream(&device, &config, sample_format, move |data| {
+                if let Ok(mut consumer) = consumer_ref.lock() {
+                    if let Some(consumer) = consumer.as_mut() {
+                        consumer(data);
+                    }
+                }
+            })
+            .and_then(|stream| {
+                stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+                Ok(stream)
+            });
+            let stream = match stream {
+                Ok(stream) => {
+                    let _ = opened_tx.send(Ok(()));
+                    stream
+                }
+                Err(e) => {
+                    let _ = opened_tx.send(Err(e));
+                    return;
+                }
+            };
+
+            // Runs until released, or until it has sat unused for too long
+            let check = Duration::from_millis(IDLE_CHECK_MS);
+            while let Err(RecvTimeoutError::Timeout) = released.recv_timeout(check) {
+                let idle = idle_consumer.lock().map(|consumer| consumer.is_none()).unwrap_or(true);
+                let unused_for = last_used_ref.lock().map(|time| time.elapsed()).unwrap_or_default();
+                if idle && unused_for >= idle_timeout {
+                    break;
+                }
+            }
+            drop(stream);
+        });
+
+        opened
+            .recv()
+            .map_err(|_| "Input stream thread exited unexpectedly".to_string())??;
+        Ok(Self {
+            key,
+            consumer,
+            last_used,
+            release,
+            thread,
+        })
+    }
+
+    pub fn key(&self) -> &StreamKey {
+        &self.key
+    }
+
+    // False once the stream has closed itself after the idle timeout
+    pub fn is_open(&self) -> bool {
+        !self.thread.is_finished()
+    }
+
+    // Route samples to `consumer` from the next callback on
+    pub fn attach(&self, consumer: InputConsumer) -> Result<(), String> {
+        *self.consumer.lock().map_err(|e| e.to_string())? = Some(consumer);
+        Ok(())
+    }
+
+    // Go back to discarding samples; the idle timeout counts from here
+    pub fn detach(&self) -> Result<(), String> {
+        *self.consumer.lock().map_err(|e| e.to_string())? = None;
+        *self.last_used.lock().map_err(|e| e.to_string())? = Instant::now();
+        Ok(())
+    }
+
+    // Close the stream, waiting for the device to be let go
+    pub fn release(self) {
+        let _ = self.release.send(());
+        let _ = self.thread.join();
+    }
+}
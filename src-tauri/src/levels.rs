@@ -0,0 +1,114 @@
+// Floor used instead of -inf so levels stay representable in JSON
+pub const MIN_DBFS: f32 = -120.0;
+
+pub fn amplitude_to_dbfs(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return MIN_DBFS;
+    }
+    (20.0 * amplitude.log10()).max(MIN_DBFS)
+}
+
+// Running per-channel peak and RMS over interleaved f32 samples
+#[derive(Debug, Clone)]
+pub struct ChannelLevels {
+    peaks: Vec<f32>,
+    sum_squares: Vec<f64>,
+    frames: u64,
+}
+
+impl ChannelLevels {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            peaks: vec![0.0; channels],
+            sum_squares: vec![0.0; channels],
+            frames: 0,
+        }
+    }
+
+    pub fn push_interleaved(&mut self, data: &[f32]) {
+        let channels = self.peaks.len();
+        if channels == 0 {
+            return;
+        }
+        for frame in data.chunks_exact(channels) {
+            for (channel, &sample) in frame.iter().enumerate() {
+                let magnitude = sample.abs();
+                if magnitude > self.peaks[channel] {
+                    self.peaks[channel] = magnitude;
+                }
+                self.sum_squares[channel] += (sample as f64) * (sample as f64);
+            }
+            self.frames += 1;
+        }
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn peak_dbfs(&self) -> Vec<f32> {
+        self.peaks.iter().map(|&peak| amplitude_to_dbfs(peak)).collect()
+    }
+
+    pub fn rms_dbfs(&self) -> Vec<f32> {
+        self.sum_squares
+            .iter()
+            .map(|&sum| {
+                if self.frames == 0 {
+                    return MIN_DBFS;
+                }
+                amplitude_to_dbfs((sum / self.frames as f64).sqrt() as f32)
+            })
+            .collect()
+    }
+}
+
+// RMS of consecutive fixed-length blocks across all channels, used to tell
+// programme material from the noise floor
+#[derive(Debug, Clone)]
+pub struct BlockRms {
+    block_samples: usize,
+    sum_squares: f64,
+    count: usize,
+    blocks: Vec<f32>,
+}
+
+impl BlockRms {
+    pub fn new(block_frames: usize, channels: usize) -> Self {
+        Self {
+            block_samples: (block_frames * channels).max(1),
+            sum_squares: 0.0,
+            count: 0,
+            blocks: Vec::new(),
+        }
+    }
+
+    pub fn push_interleaved(&mut self, data: &[f32]) {
+        for &sample in data {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.count += 1;
+            if self.count == self.block_samples {
+                let rms = (self.sum_squares / self.count as f64).sqrt() as f32;
+                self.blocks.push(amplitude_to_dbfs(rms));
+                self.sum_squares = 0.0;
+                self.count = 0;
+            }
+        }
+    }
+
+    // Level of complete blocks in dBFS; a trailing partial block is dropped
+    pub fn blocks_dbfs(&self) -> &[f32] {
+        &self.blocks
+    }
+}
+
+// Value below which `fraction` of the levels fall, or MIN_DBFS when empty
+pub fn percentile_dbfs(levels: &[f32], fraction: f32) -> f32 {
+    if levels.is_empty() {
+        return MIN_DBFS;
+    }
+    let mut sorted = levels.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let index = ((sorted.len() - 1) as f32 * fraction.clamp(0.0, 1.0)).round() as usize;
+    sorted[index]
+}
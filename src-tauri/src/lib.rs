@@ -1,14 +1,102 @@
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use hound::{WavSpec, WavWriter};
-use tauri::{State, Manager};
+use std::time::{Duration, Instant, SystemTime};
+use serde::{Deserialize, Serialize};
+use tauri::ipc::Channel;
+use tauri::{Emitter, State, Manager};
+
+mod aiff;
+mod calibrate;
+mod capture;
+mod channels;
+mod chunk;
+mod clip;
+mod condense;
+mod convert;
+mod decode;
+mod denoise;
+mod diagnostics;
+mod disk;
+mod dsp;
+mod dynamics;
+mod encode;
+mod eq;
+mod export;
+mod feedback;
+mod filters;
+mod fingerprint;
+mod format;
+mod frames;
+mod keepalive;
+mod levels;
+mod manifest;
+mod master;
+mod meter;
+mod metadata;
+mod monitor;
+mod permissions;
+mod pitch;
+mod pipeline;
+mod playback;
+mod preflight;
+mod probe;
+mod punch;
+mod rawpcm;
+mod recorder;
+mod report;
+mod schedule;
+mod session;
+mod stereo;
+mod sweep;
+mod tags;
+mod timeline;
+mod transcription;
+mod wav;
+mod waveform;
+
+use capture::DeviceRole;
+use condense::CondenseSettings;
+use feedback::FeedbackTone;
+use filters::{FilterSettings, FilterToggles};
+use format::{AutoFormatPolicy, BitrateMode, LowSpacePolicy, OutputFormat, QualityPreset};
+use frames::{AudioFrame, FrameStream, FrameStreamOptions};
+use keepalive::KeptInput;
+use master::MasterSpec;
+use permissions::FilePermissions;
+use recorder::{AutoGainSettings, RecorderConfig, SampleRateRequest, SegmentAlign};
+use session::Session;
+
+const MAX_DISCARD_MS: u64 = 5_000;
+const MAX_POST_ROLL_MS: u64 = 5_000;
+const DEFAULT_STREAM_IDLE_SECS: u64 = 300;
+const MAX_AUTO_GAIN_DB: f32 = 40.0;
+const DEFAULT_RECONNECT_TIMEOUT_SECS: u64 = 60;
+const MAX_RECONNECT_TIMEOUT_SECS: u64 = 3_600;
+
+// Handle to the thread that captures and finalizes a recording
+type RecordingThread = thread::JoinHandle<Result<(), String>>;
 
 // State to manage recording
 pub struct RecordingState {
     pub is_recording: Arc<Mutex<bool>>,
     pub output_path: Arc<Mutex<Option<String>>>,
+    pub recording_thread: Arc<Mutex<Option<RecordingThread>>>,
+    // Audible start/stop/error tones; off unless the user enables them
+    pub feedback_enabled: Arc<Mutex<bool>>,
+    // Reported by the frontend; live level frames pause while hidden
+    pub window_visible: Arc<Mutex<bool>>,
+    // Input stream kept open between recordings in keep_stream_alive mode
+    pub kept_input: Arc<Mutex<Option<KeptInput>>>,
+    // The current or most recent recording, for state snapshots
+    pub session: Arc<Mutex<Option<Session>>>,
+    // Passthrough level, kept across recordings; see monitor::set_monitor_gain
+    pub monitor_gain: Arc<Mutex<f32>>,
+    // Consolidated recording-state events; see session::set_state_events
+    pub state_events: Arc<Mutex<bool>>,
+    // Live filter stages; see filters::set_filter_enabled
+    pub filters: Arc<FilterToggles>,
+    // Device requested by switch_device, taken up by the recording thread
+    pub device_switch: Arc<Mutex<Option<String>>>,
 }
 
 impl Default for RecordingState {
@@ -16,10 +104,83 @@ impl Default for RecordingState {
         Self {
             is_recording: Arc::new(Mutex::new(false)),
             output_path: Arc::new(Mutex::new(None)),
+            recording_thread: Arc::new(Mutex::new(None)),
+            feedback_enabled: Arc::new(Mutex::new(false)),
+            window_visible: Arc::new(Mutex::new(true)),
+            kept_input: Arc::new(Mutex::new(None)),
+            session: Arc::new(Mutex::new(None)),
+            monitor_gain: Arc::new(Mutex::new(monitor::DEFAULT_MONITOR_GAIN)),
+            state_events: Arc::new(Mutex::new(false)),
+            filters: Arc::new(FilterToggles::default()),
+            device_switch: Arc::new(Mutex::new(None)),
         }
     }
 }
 
+// Options accepted by start_recording; everything is optional so the
+// frontend can keep calling it without arguments
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordingOptions {
+    // Which default input to record from; only Windows tells them apart
+    pub device_role: Option<DeviceRole>,
+    // Input names to try in order, e.g. a USB mic then the built-in one; the
+    // first that is plugged in and supports the requested config is used
+    pub device_priority: Vec<String>,
+    pub preset: Option<QualityPreset>,
+    // Overrides the preset's format, e.g. AIFF for macOS tools
+    pub format: Option<OutputFormat>,
+    // Overrides the preset's constant bitrate for compressed output
+    pub bitrate: Option<BitrateMode>,
+    // When set, the preset is chosen from free disk space instead
+    pub auto_format: Option<AutoFormatPolicy>,
+    // During long recordings, step down to a smaller preset whenever free
+    // space falls below the policy's floor, splitting at the switch
+    pub low_space: Option<LowSpacePolicy>,
+    // Split into files whose boundaries land on the wall clock
+    pub segment_align: Option<SegmentAlign>,
+    // Capture at a specific rate instead of the device default
+    pub sample_rate: Option<SampleRateRequest>,
+    // Skip driver start-up noise; off unless set
+    pub discard_initial_ms: Option<u64>,
+    // Fixed device buffer in frames, e.g. from calibrate_buffer_size; the
+    // host picks one when unset
+    pub buffer_frames: Option<u32>,
+    // Keep capturing this long after stop is requested so a slightly early
+    // stop doesn't clip the last word; off unless set
+    pub stop_post_roll_ms: Option<u64>,
+    // Channels in the file; a mono input can be written as stereo and so on
+    pub output_channels: Option<u16>,
+    // Even out the level as it records. The gain carries on across splits,
+    // and each segment's metadata keeps how it moved.
+    pub auto_gain: Option<AutoGainSettings>,
+    // A name per channel, kept in the metadata and embedded in WAV output
+    pub channel_labels: Option<Vec<String>>,
+    // Boost highs with a first-order filter y[n] = x[n] - a*x[n-1], e.g.
+    // 0.95; the coefficient is kept in the metadata so export can undo it
+    pub pre_emphasis: Option<f32>,
+    // Gain, highpass, noise gate and soft clip, each switchable mid-take with
+    // set_filter_enabled; all off unless listed as enabled
+    pub filters: FilterSettings,
+    // After each segment, also write a trimmed, normalized, compressed copy
+    // for sharing next to it; the recording itself is left untouched
+    pub auto_master: Option<MasterSpec>,
+    // Play the input on the default output while recording, at the level
+    // set by set_monitor_gain
+    pub monitor: bool,
+    // Mode bits (and on Unix, owner) for the finished recording, its
+    // metadata and master, e.g. 0o640 on a shared machine
+    pub file_permissions: Option<FilePermissions>,
+    // Leave the input open after stopping so the next recording starts
+    // instantly; closed by release_device or after the idle timeout
+    pub keep_stream_alive: bool,
+    pub stream_idle_timeout_secs: Option<u64>,
+    // If the device disappears, wait for it to come back under the same name
+    // and carry on in a new segment instead of ending the recording
+    pub auto_reconnect: bool,
+    pub reconnect_timeout_secs: Option<u64>,
+}
+
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
 fn greet(name: &str) -> String {
@@ -27,14 +188,51 @@ fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-async fn start_recording(app_handle: tauri::AppHandle, state: State<'_, RecordingState>) -> Result<String, String> {
+async fn start_recording(
+    app_handle: tauri::AppHandle,
+    state: State<'_, RecordingState>,
+    options: Option<RecordingOptions>,
+) -> Result<String, String> {
+    begin_recording(app_handle, &state, options.unwrap_or_default(), None, None)
+}
+
+// Same as start_recording, but also streams live frames over `on_frame`,
+// which is much cheaper than repeated events for high-rate data
+#[tauri::command]
+async fn start_recording_streamed(
+    app_handle: tauri::AppHandle,
+    state: State<'_, RecordingState>,
+    options: Option<RecordingOptions>,
+    stream: Option<FrameStreamOptions>,
+    on_frame: Channel<AudioFrame>,
+) -> Result<String, String> {
+    let frame_stream = FrameStream {
+        channel: on_frame,
+        options: stream.unwrap_or_default(),
+    };
+    begin_recording(app_handle, &state, options.unwrap_or_default(), Some(frame_stream), None)
+}
+
+// `name` is the file name in app data to record under, without extension;
+// "recording" when unset, replacing the previous take
+fn begin_recording(
+    app_handle: tauri::AppHandle,
+    state: &RecordingState,
+    options: RecordingOptions,
+    frame_stream: Option<FrameStream>,
+    name: Option<String>,
+) -> Result<String, String> {
     let mut is_recording = state.is_recording.lock().map_err(|e| e.to_string())?;
     
     if *is_recording {
         return Err("Already recording".to_string());
     }
-    
-    *is_recording = true;
+    if let Some(handle) = state.recording_thread.lock().map_err(|e| e.to_string())?.as_ref() {
+        if !handle.is_finished() {
+            return Err("The previous recording is still being finalized".to_string());
+        }
+    }
+    let session = Session::new(options.clone())?;
     
     // Get the app data directory using Tauri 2.0 API
     let app_data_dir = app_handle.path().app_data_dir()
@@ -45,162 +243,300 @@ async fn start_recording(app_handle: tauri::AppHandle, state: State<'_, Recordin
         return Err(format!("Failed to create app data directory: {}", e));
     }
     
-    let output_path = app_data_dir.join("recording.wav");
-    let output_path_str = output_path.to_string_lossy().to_string();
+    if let Some(error) = preflight::option_checks(&options).into_iter().find_map(|check| check.error) {
+        return Err(error);
+    }
+    let device_role = options.device_role.unwrap_or_default();
+    // The device the recording thread will open, so settings that don't fit
+    // it fail here instead of after start has returned
+    let (_, supported, _) = recorder::select_device(
+        device_role,
+        &options.device_priority,
+        options.sample_rate,
+        options.buffer_frames,
+    )?;
+    let (output_format, preset_kbps) = match (&options.auto_format, options.format) {
+        (Some(policy), _) => {
+            // Sized at the rate and channel count the file will have
+            let sample_rate = options
+                .sample_rate
+                .filter(|request| request.resample)
+                .map_or(supported.sample_rate().0, |request| request.rate);
+            let channels = options.output_channels.unwrap_or(supported.channels());
+            let free_bytes = disk::available_space(&app_data_dir)?;
+            let choice = format::choose_format(free_bytes, sample_rate, channels, policy);
+            let _ = app_handle.emit("format-selected", choice.clone());
+            (choice.format, choice.preset.bitrate_kbps())
+        }
+        (None, Some(format)) => (format, options.preset.and_then(QualityPreset::bitrate_kbps)),
+        (None, None) => {
+            let preset = options.preset.unwrap_or(QualityPreset::Lossless);
+            (preset.format(), preset.bitrate_kbps())
+        }
+    };
+    output_format.ensure_available()?;
+    let channel_checks = preflight::channel_checks(supported.channels(), &options, Some(output_format));
+    if let Some(error) = channel_checks.into_iter().find_map(|check| check.error) {
+        return Err(error);
+    }
+    // With auto_format, a bitrate only applies if a compressed preset is picked
+    let bitrate = match options.bitrate {
+        Some(_) if options.auto_format.is_some() && output_format.is_pcm() => None,
+        Some(mode) => {
+            mode.validate(output_format)?;
+            Some(mode)
+        }
+        None if output_format.is_pcm() => None,
+        None => preset_kbps.map(BitrateMode::Cbr),
+    };
+    let discard_initial_ms = options.discard_initial_ms.unwrap_or(0);
+    let stop_post_roll_ms = options.stop_post_roll_ms.unwrap_or(0);
+    let reconnect_timeout_secs = options.reconnect_timeout_secs.unwrap_or(DEFAULT_RECONNECT_TIMEOUT_SECS);
+    
+    // Capture always goes to WAV; other formats are encoded from it per segment
+    let config = RecorderConfig {
+        base_path: app_data_dir.join(name.as_deref().unwrap_or("recording")),
+        device_role,
+        device_priority: options.device_priority.clone(),
+        output_format,
+        bitrate,
+        low_space: options.low_space,
+        segment_align: options.segment_align,
+        frame_stream,
+        window_visible: state.window_visible.clone(),
+        sample_rate: options.sample_rate,
+        discard_initial_ms,
+        buffer_frames: options.buffer_frames,
+        stop_post_roll: Duration::from_millis(stop_post_roll_ms),
+        output_channels: options.output_channels,
+        auto_gain: options.auto_gain,
+        channel_labels: options.channel_labels.clone(),
+        pre_emphasis: options.pre_emphasis,
+        filters: options.filters.clone(),
+        filter_toggles: state.filters.clone(),
+        auto_master: options.auto_master.clone(),
+        monitor_gain: options.monitor.then(|| state.monitor_gain.clone()),
+        file_permissions: options.file_permissions,
+        kept_input: state.kept_input.clone(),
+        session: state.session.clone(),
+        keep_alive_idle: options.keep_stream_alive.then(|| {
+            Duration::from_secs(options.stream_idle_timeout_secs.unwrap_or(DEFAULT_STREAM_IDLE_SECS))
+        }),
+        reconnect_timeout: options
+            .auto_reconnect
+            .then(|| Duration::from_secs(reconnect_timeout_secs)),
+        device_switch: state.device_switch.clone(),
+    };
+    let output_path_str = config.first_output_path().to_string_lossy().to_string();
     
     *state.output_path.lock().map_err(|e| e.to_string())? = Some(output_path_str.clone());
+    *state.session.lock().map_err(|e| e.to_string())? = Some(session);
+    state.filters.reset(&options.filters.enabled);
+    *state.device_switch.lock().map_err(|e| e.to_string())? = None;
+    *is_recording = true;
     
     let is_recording_clone = state.is_recording.clone();
-    let output_path_clone = output_path.clone();
+    let is_recording_failed = state.is_recording.clone();
+    let output_path_clone = state.output_path.clone();
+    let feedback = *state.feedback_enabled.lock().map_err(|e| e.to_string())?;
+    let session_clone = state.session.clone();
     
     // Start recording in a separate thread
-    thread::spawn(move || {
-        if let Err(e) = record_audio(is_recording_clone, output_path_clone) {
-            eprintln!("Recording error: {}", e);
+    let handle = thread::spawn(move || {
+        // The start tone finishes before the input opens so it isn't captured
+        if feedback {
+            if let Err(e) = feedback::play_blocking(FeedbackTone::Start) {
+                eprintln!("Feedback tone error: {}", e);
+            }
         }
+        recorder::record_audio(app_handle, is_recording_clone, output_path_clone, config).inspect_err(|e| {
+            eprintln!("Recording error: {}", e);
+            // Nothing is recording any more, whatever stop would have found
+            if let Ok(mut recording) = is_recording_failed.lock() {
+                *recording = false;
+            }
+            session::update(&session_clone, |session| {
+                session.stopped_unix_ms.get_or_insert(recorder::unix_ms(SystemTime::now()));
+                session.warnings.push(format!("Recording failed: {}", e));
+            });
+            if feedback {
+                feedback::play_in_background(FeedbackTone::Error);
+            }
+        })
     });
+    *state.recording_thread.lock().map_err(|e| e.to_string())? = Some(handle);
     
     Ok(output_path_str)
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StopOptions {
+    // How long to wait quietly before reporting finalize progress
+    pub finalize_timeout_ms: u64,
+    // Give up after this long; the recording thread keeps going regardless
+    pub hard_timeout_ms: u64,
+    // Afterwards, also write a copy of the recording (the last segment when
+    // splitting) with long pauses shortened
+    pub condense_silence: Option<CondenseSettings>,
+}
+
+impl Default for StopOptions {
+    fn default() -> Self {
+        Self {
+            finalize_timeout_ms: 2_000,
+            hard_timeout_ms: 120_000,
+            condense_silence: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FinalizingProgress {
+    elapsed_ms: u64,
+    hard_timeout_ms: u64,
+    path: Option<String>,
+}
+
+const FINALIZE_POLL_MS: u64 = 100;
+const FINALIZE_PROGRESS_MS: u64 = 500;
+
 #[tauri::command]
-async fn stop_recording(state: State<'_, RecordingState>) -> Result<String, String> {
-    let mut is_recording = state.is_recording.lock().map_err(|e| e.to_string())?;
-    
-    if !*is_recording {
-        return Err("Not recording".to_string());
+async fn stop_recording(
+    app_handle: tauri::AppHandle,
+    options: Option<StopOptions>,
+) -> Result<String, String> {
+    // Waiting for the file can take minutes, so it stays off the async workers
+    tauri::async_runtime::spawn_blocking(move || {
+        end_recording(&app_handle, &app_handle.state::<RecordingState>(), options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Failed to stop recording: {}", e))?
+}
+
+fn end_recording(
+    app_handle: &tauri::AppHandle,
+    state: &RecordingState,
+    options: StopOptions,
+) -> Result<String, String> {
+    if let Some(settings) = options.condense_silence.as_ref() {
+        settings.validate()?;
+    }
+    {
+        let mut is_recording = state.is_recording.lock().map_err(|e| e.to_string())?;
+        
+        if !*is_recording {
+            return Err("Not recording".to_string());
+        }
+        
+        *is_recording = false;
     }
+    session::update(&state.session, |session| {
+        session.stopped_unix_ms = Some(recorder::unix_ms(SystemTime::now()));
+    });
     
-    *is_recording = false;
+    // Wait for the recording thread to finalize (and encode) the file. Slow
+    // media can take a while, so report progress instead of returning early.
+    let handle = state.recording_thread.lock().map_err(|e| e.to_string())?.take();
+    if let Some(handle) = handle {
+        let started = Instant::now();
+        let mut last_progress = 0;
+        while !handle.is_finished() {
+            let elapsed_ms = started.elapsed().as_millis() as u64;
+            if elapsed_ms >= options.hard_timeout_ms {
+                // Keep the handle so a new recording can't start on top of it
+                *state.recording_thread.lock().map_err(|e| e.to_string())? = Some(handle);
+                return Err(format!(
+                    "Recording is still being finalized after {} s; the file may be incomplete",
+                    elapsed_ms / 1000
+                ));
+            }
+            if elapsed_ms >= options.finalize_timeout_ms && elapsed_ms - last_progress >= FINALIZE_PROGRESS_MS {
+                last_progress = elapsed_ms;
+                let path = state.output_path.lock().map_err(|e| e.to_string())?.clone();
+                let _ = app_handle.emit(
+                    "finalizing-progress",
+                    FinalizingProgress {
+                        elapsed_ms,
+                        hard_timeout_ms: options.hard_timeout_ms,
+                        path,
+                    },
+                );
+            }
+            thread::sleep(Duration::from_millis(FINALIZE_POLL_MS));
+        }
+        handle.join().map_err(|_| "Recording thread panicked".to_string())??;
+    }
     
-    // Wait a bit for the recording to stop
-    thread::sleep(Duration::from_millis(100));
+    // The input stream is closed by now, so the tone can't end up in the file
+    if *state.feedback_enabled.lock().map_err(|e| e.to_string())? {
+        feedback::play_in_background(FeedbackTone::Stop);
+    }
     
     let output_path = state.output_path.lock().map_err(|e| e.to_string())?;
     match output_path.as_ref() {
-        Some(path) => Ok(path.clone()),
+        Some(path) => {
+            if let Some(settings) = options.condense_silence {
+                condense::spawn_condense(app_handle.clone(), state.session.clone(), path.into(), settings);
+            }
+            Ok(path.clone())
+        }
         None => Err("No recording path found".to_string()),
     }
 }
 
+// Move the running recording to another input, e.g. from a podium mic to a
+// lapel mic. The current segment is finished and the new device records into
+// the next one; "device-switched" follows once it is capturing, or
+// "device-switch-failed" if it can't be used and the old device carries on.
+#[tauri::command]
+async fn switch_device(state: State<'_, RecordingState>, device_name: String) -> Result<(), String> {
+    if !*state.is_recording.lock().map_err(|e| e.to_string())? {
+        return Err("Not recording".to_string());
+    }
+    let current = state
+        .session
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .and_then(|session| session.device.clone());
+    if current.as_deref() == Some(device_name.as_str()) {
+        return Err(format!("Already recording from {}", device_name));
+    }
+    capture::find_input_device(Some(&device_name))?;
+    *state.device_switch.lock().map_err(|e| e.to_string())? = Some(device_name);
+    Ok(())
+}
+
 #[tauri::command]
 async fn is_recording(state: State<'_, RecordingState>) -> Result<bool, String> {
     let is_recording = state.is_recording.lock().map_err(|e| e.to_string())?;
     Ok(*is_recording)
 }
 
-fn record_audio(is_recording: Arc<Mutex<bool>>, output_path: std::path::PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let host = cpal::default_host();
-    let device = host.default_input_device().ok_or("No input device available")?;
-    
-    let config = device.default_input_config()?;
-    let sample_format = config.sample_format();
-    let config: cpal::StreamConfig = config.into();
-    
-    let spec = WavSpec {
-        channels: config.channels as _,
-        sample_rate: config.sample_rate.0 as _,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
-    };
-    
-    let writer = WavWriter::create(output_path, spec)?;
-    let writer = Arc::new(Mutex::new(Some(writer)));
-    
-    let stream = match sample_format {
-        cpal::SampleFormat::F32 => {
-            let writer_ref = writer.clone();
-            let is_recording_ref = is_recording.clone();
-            device.build_input_stream(
-                &config,
-                move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                    let recording = is_recording_ref.lock().unwrap();
-                    if !*recording {
-                        return;
-                    }
-                    
-                    if let Ok(mut writer_guard) = writer_ref.lock() {
-                        if let Some(ref mut writer) = writer_guard.as_mut() {
-                            for &sample in data {
-                                let sample = (sample * i16::MAX as f32) as i16;
-                                let _ = writer.write_sample(sample);
-                            }
-                        }
-                    }
-                },
-                |err| eprintln!("Stream error: {}", err),
-                None,
-            )?
-        }
-        cpal::SampleFormat::I16 => {
-            let writer_ref = writer.clone();
-            let is_recording_ref = is_recording.clone();
-            device.build_input_stream(
-                &config,
-                move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                    let recording = is_recording_ref.lock().unwrap();
-                    if !*recording {
-                        return;
-                    }
-                    
-                    if let Ok(mut writer_guard) = writer_ref.lock() {
-                        if let Some(ref mut writer) = writer_guard.as_mut() {
-                            for &sample in data {
-                                let _ = writer.write_sample(sample);
-                            }
-                        }
-                    }
-                },
-                |err| eprintln!("Stream error: {}", err),
-                None,
-            )?
-        }
-        cpal::SampleFormat::U16 => {
-            let writer_ref = writer.clone();
-            let is_recording_ref = is_recording.clone();
-            device.build_input_stream(
-                &config,
-                move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                    let recording = is_recording_ref.lock().unwrap();
-                    if !*recording {
-                        return;
-                    }
-                    
-                    if let Ok(mut writer_guard) = writer_ref.lock() {
-                        if let Some(ref mut writer) = writer_guard.as_mut() {
-                            for &sample in data {
-                                let sample = (sample as i32 - 32768) as i16;
-                                let _ = writer.write_sample(sample);
-                            }
-                        }
-                    }
-                },
-                |err| eprintln!("Stream error: {}", err),
-                None,
-            )?
-        }
-        _ => return Err("Unsupported sample format".into()),
-    };
-    
-    stream.play()?;
-    
-    // Keep the stream alive while recording
-    loop {
-        thread::sleep(Duration::from_millis(100));
-        let recording = is_recording.lock().unwrap();
-        if !*recording {
-            break;
-        }
+// Called by the frontend on visibility changes so the backend can stop
+// emitting live updates nobody can see, without affecting the recording
+#[tauri::command]
+async fn set_window_visible(state: State<'_, RecordingState>, visible: bool) -> Result<(), String> {
+    *state.window_visible.lock().map_err(|e| e.to_string())? = visible;
+    Ok(())
+}
+
+// Close an input stream left open by keep_stream_alive
+#[tauri::command]
+async fn release_device(state: State<'_, RecordingState>) -> Result<bool, String> {
+    if *state.is_recording.lock().map_err(|e| e.to_string())? {
+        return Err("Cannot release the input device while recording".to_string());
     }
-    
-    // Finalize the WAV file
-    if let Ok(mut writer_guard) = writer.lock() {
-        if let Some(writer) = writer_guard.take() {
-            let _ = writer.finalize();
+    let kept = state.kept_input.lock().map_err(|e| e.to_string())?.take();
+    Ok(match kept {
+        Some(input) => {
+            input.release();
+            true
         }
-    }
-    
-    Ok(())
+        None => false,
+    })
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -209,11 +545,71 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
         .manage(RecordingState::default())
+        .setup(|app| {
+            let app_data_dir = app.path().app_data_dir()?;
+            std::fs::create_dir_all(&app_data_dir)?;
+            app.manage(schedule::Scheduler::load(schedule::schedule_path(&app_data_dir)));
+            schedule::spawn(app.handle().clone());
+            session::spawn_state_events(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             start_recording,
+            start_recording_streamed,
             stop_recording,
-            is_recording
+            switch_device,
+            is_recording,
+            set_window_visible,
+            release_device,
+            meter::meter_once,
+            diagnostics::test_microphone,
+            diagnostics::raw_default_config,
+            export::export_recording,
+            feedback::list_feedback_tones,
+            feedback::play_feedback_tone,
+            feedback::set_feedback_enabled,
+            probe::audio_bitrate,
+            sweep::record_sweep,
+            sweep::compute_impulse_response,
+            pitch::detect_pitch,
+            pitch::pitch_track,
+            tags::set_tags,
+            tags::get_tags,
+            punch::punch_record,
+            waveform::waveform_svg_path,
+            denoise::capture_noise_profile,
+            denoise::denoise,
+            stereo::stereo_correlation,
+            schedule::schedule_recording,
+            schedule::list_scheduled,
+            schedule::cancel_scheduled,
+            convert::convert,
+            manifest::create_manifest,
+            manifest::verify_manifest,
+            pipeline::process,
+            channels::detect_dead_channels,
+            clip::make_clip,
+            calibrate::calibrate_buffer_size,
+            session::state_snapshot,
+            session::restore_from_snapshot,
+            metadata::read_recording_metadata,
+            chunk::chunk_recording,
+            metadata::measured_sample_rate,
+            monitor::set_monitor_gain,
+            rawpcm::guess_pcm_params,
+            rawpcm::wrap_pcm_as_wav,
+            session::set_state_events,
+            fingerprint::audio_fingerprint,
+            fingerprint::compare_fingerprints,
+            timeline::export_timeline,
+            filters::set_filter_enabled,
+            filters::get_filter_chain,
+            transcription::estimate_transcription,
+            dynamics::dynamic_range,
+            condense::condense_silence,
+            preflight::can_record,
+            report::recording_report
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
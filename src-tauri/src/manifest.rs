@@ -0,0 +1,316 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::decode;
+use crate::probe;
+
+const MANIFEST_NAME: &str = "manifest.json";
+const KEY_NAME: &str = "manifest.key";
+const MANIFEST_VERSION: u32 = 1;
+const AUDIO_EXTENSIONS: [&str; 7] = ["wav", "aiff", "aif", "mp3", "flac", "ogg", "opus"];
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    // Relative to the manifest's directory, with `/` separators
+    pub file: String,
+    pub bytes: u64,
+    pub sha256: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: Option<u16>,
+    pub duration_secs: f64,
+}
+
+// The signature is an HMAC-SHA256 over the manifest with `signature` empty,
+// keyed per install, so a manifest edited to match altered files is caught.
+// Anyone holding the key file can re-sign, so this guards against bit rot and
+// casual tampering, not a determined local attacker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    version: u32,
+    created_unix_ms: u64,
+    files: Vec<ManifestEntry>,
+    signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestSummary {
+    pub path: String,
+    pub files: usize,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlteredFile {
+    pub file: String,
+    pub expected_sha256: String,
+    pub actual_sha256: String,
+    pub expected_bytes: u64,
+    pub actual_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorruptFile {
+    pub file: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestReport {
+    pub signature_valid: bool,
+    pub checked: usize,
+    pub missing: Vec<String>,
+    // Contents differ from the checksum but the file still reads as audio
+    pub altered: Vec<AlteredFile>,
+    // No longer readable as audio
+    pub corrupt: Vec<CorruptFile>,
+    // Recordings in the directory the manifest doesn't list
+    pub unlisted: Vec<String>,
+    pub clean: bool,
+}
+
+// Checksum every recording under `dir` into a signed `manifest.json` there
+#[tauri::command]
+pub async fn create_manifest(app_handle: tauri::AppHandle, dir: String) -> Result<ManifestSummary, String> {
+    let dir = Path::new(&dir);
+    let key = signing_key(&app_handle)?;
+    let mut files = Vec::new();
+    for file in audio_files(dir)? {
+        files.push(describe(dir, &file)?);
+    }
+    let mut manifest = Manifest {
+        version: MANIFEST_VERSION,
+        created_unix_ms: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        files,
+        signature: String::new(),
+    };
+    manifest.signature = hex(&sign(&key, &manifest)?.finalize().into_bytes());
+
+    let path = dir.join(MANIFEST_NAME);
+    let json = serde_json::to_string_pretty(&manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write manifest: {}", e))?;
+    Ok(ManifestSummary {
+        path: path.to_string_lossy().to_string(),
+        files: manifest.files.len(),
+        total_bytes: manifest.files.iter().map(|entry| entry.bytes).sum(),
+    })
+}
+
+// Re-check every file in `dir` against its manifest
+#[tauri::command]
+pub async fn verify_manifest(app_handle: tauri::AppHandle, dir: String) -> Result<ManifestReport, String> {
+    let dir = Path::new(&dir);
+    let json = std::fs::read_to_string(dir.join(MANIFEST_NAME)).map_err(|e| format!("Failed to read manifest: {}", e))?;
+    let mut manifest: Manifest = serde_json::from_str(&json).map_err(|e| format!("Failed to parse manifest: {}", e))?;
+    if manifest.version != MANIFEST_VERSION {
+        return Err(format!("Unsupported manifest version {}", manifest.version));
+    }
+
+    let key = signing_key(&app_handle)?;
+    let signature = std::mem::take(&mut manifest.signature);
+    let signature_valid = match unhex(&signature) {
+        Some(bytes) => sign(&key, &manifest)?.verify_slice(&bytes).is_ok(),
+        None => false,
+    };
+
+    let mut listed: BTreeMap<String, ManifestEntry> =
+        manifest.files.into_iter().map(|entry| (entry.file.clone(), entry)).collect();
+    let mut report = ManifestReport {
+        signature_valid,
+        checked: 0,
+        missing: Vec::new(),
+        altered: Vec::new(),
+        corrupt: Vec::new(),
+        unlisted: Vec::new(),
+        clean: false,
+    };
+    for file in audio_files(dir)? {
+        let name = relative_name(dir, &file);
+        let Some(expected) = listed.remove(&name) else {
+            report.unlisted.push(name);
+            continue;
+        };
+        report.checked += 1;
+        let (bytes, sha256) = checksum(&file)?;
+        if bytes == expected.bytes && sha256 == expected.sha256 {
+            continue;
+        }
+        if let Err(error) = read_spec(&file) {
+            report.corrupt.push(CorruptFile { file: name, error });
+        } else {
+            report.altered.push(AlteredFile {
+                file: name,
+                expected_sha256: expected.sha256,
+                actual_sha256: sha256,
+                expected_bytes: expected.bytes,
+                actual_bytes: bytes,
+            });
+        }
+    }
+    report.missing = listed.into_keys().collect();
+    report.clean = report.signature_valid
+        && report.missing.is_empty()
+        && report.altered.is_empty()
+        && report.corrupt.is_empty()
+        && report.unlisted.is_empty();
+    Ok(report)
+}
+
+// Recordings under `dir`, recursively, in a stable order
+fn audio_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current).map_err(|e| format!("Failed to read directory: {}", e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| format!("Failed to read directory: {}", e))?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+            {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn relative_name(dir: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(dir).unwrap_or(file);
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn describe(dir: &Path, file: &Path) -> Result<ManifestEntry, String> {
+    let (bytes, sha256) = checksum(file)?;
+    let name = relative_name(dir, file);
+    let spec = read_spec(file).map_err(|e| format!("{}: {}", name, e))?;
+    Ok(ManifestEntry {
+        file: name,
+        bytes,
+        sha256,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        bits_per_sample: spec.bits_per_sample,
+        duration_secs: spec.duration_secs,
+    })
+}
+
+struct AudioSpec {
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: Option<u16>,
+    duration_secs: f64,
+}
+
+// Format details from the decoder, or from the header alone for formats that
+// can't be decoded (Opus)
+fn read_spec(file: &Path) -> Result<AudioSpec, String> {
+    match decode::open(file) {
+        Ok(source) => {
+            let info = source.info();
+            let duration_secs = match info.frames {
+                Some(frames) => frames as f64 / info.sample_rate as f64,
+                None => probe::probe(file).map(|info| info.duration_secs).unwrap_or(0.0),
+            };
+            Ok(AudioSpec {
+                sample_rate: info.sample_rate,
+                channels: info.channels,
+                bits_per_sample: info.bits_per_sample,
+                duration_secs,
+            })
+        }
+        Err(e) => probe::probe(file).map_err(|_| e).map(|info| AudioSpec {
+            sample_rate: info.sample_rate,
+            channels: info.channels,
+            bits_per_sample: None,
+            duration_secs: info.duration_secs,
+        }),
+    }
+}
+
+// Length of any recording the manifest could describe
+pub fn duration_secs(file: &Path) -> Result<f64, String> {
+    read_spec(file).map(|spec| spec.duration_secs)
+}
+
+// Size and SHA-256 of a file, read in chunks
+fn checksum(path: &Path) -> Result<(u64, String), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut bytes = 0u64;
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        bytes += read as u64;
+    }
+    Ok((bytes, hex(&hasher.finalize())))
+}
+
+fn sign(key: &[u8], manifest: &Manifest) -> Result<HmacSha256, String> {
+    let json = serde_json::to_vec(manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    let mut mac = HmacSha256::new_from_slice(key).map_err(|e| format!("Invalid manifest key: {}", e))?;
+    mac.update(&json);
+    Ok(mac)
+}
+
+// Per-install secret, created on first use
+fn signing_key(app_handle: &tauri::AppHandle) -> Result<Vec<u8>, String> {
+    let app_data_dir = app_handle.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let path = app_data_dir.join(KEY_NAME);
+    if let Ok(key) = std::fs::read(&path) {
+        if !key.is_empty() {
+            return Ok(key);
+        }
+    }
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let mut key = vec![0u8; 32];
+    getrandom::getrandom(&mut key).map_err(|e| format!("Failed to generate manifest key: {}", e))?;
+    std::fs::write(&path, &key).map_err(|e| format!("Failed to save manifest key: {}", e))?;
+    Ok(key)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
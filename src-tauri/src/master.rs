@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::decode;
+use crate::format::{BitrateMode, OutputFormat};
+use crate::levels;
+use crate::pipeline::{self, Operation};
+
+// Kept either side of the trimmed audio so the first and last words aren't
+// cut mid-breath
+const TRIM_PAD_MS: u64 = 150;
+
+// How the shareable copy made after each segment is processed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MasterSpec {
+    // Cut leading and trailing audio whose peak stays below this; no trim
+    // when unset
+    pub trim_below_dbfs: Option<f32>,
+    // Peak the master is normalized to; left at its level when unset
+    pub normalize_dbfs: Option<f32>,
+    // Highpass to take out rumble, e.g. 80 Hz
+    pub highpass_hz: Option<f32>,
+    pub format: OutputFormat,
+    pub bitrate: Option<BitrateMode>,
+}
+
+impl Default for MasterSpec {
+    fn default() -> Self {
+        Self {
+            trim_below_dbfs: Some(-50.0),
+            normalize_dbfs: Some(-1.0),
+            highpass_hz: None,
+            format: OutputFormat::Mp3,
+            bitrate: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterEvent {
+    // The untouched recording
+    pub raw_path: String,
+    pub master_path: String,
+}
+
+impl MasterSpec {
+    // Catch a bad spec before recording rather than after it
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(threshold) = self.trim_below_dbfs {
+            if !(levels::MIN_DBFS..=0.0).contains(&threshold) {
+                return Err(format!("Trim threshold must be between {} and 0 dBFS", levels::MIN_DBFS));
+            }
+        }
+        if self.highpass_hz.is_some_and(|hz| !(hz > 0.0 && hz.is_finite())) {
+            return Err("Highpass cutoff must be above 0 Hz".to_string());
+        }
+        self.format.ensure_available()?;
+        if let Some(mode) = self.bitrate {
+            mode.validate(self.format)?;
+        }
+        pipeline::validate_order(&self.operations(None)).map(|_| ())
+    }
+
+    fn operations(&self, trim: Option<(f64, f64)>) -> Vec<Operation> {
+        let mut operations = Vec::new();
+        if let Some((start_secs, end_secs)) = trim {
+            operations.push(Operation::Trim {
+                start_secs,
+                end_secs: Some(end_secs),
+            });
+        }
+        if let Some(cutoff_hz) = self.highpass_hz {
+            operations.push(Operation::Highpass { cutoff_hz, q: None });
+        }
+        if let Some(target_dbfs) = self.normalize_dbfs {
+            operations.push(Operation::Normalize { target_dbfs });
+        }
+        operations.push(Operation::Encode {
+            format: self.format,
+            bitrate: self.bitrate,
+        });
+        operations
+    }
+}
+
+// `recording.mp3` gets `recording-master.mp3` (or whatever the spec's format is)
+pub fn master_path(raw: &Path, format: OutputFormat) -> PathBuf {
+    let stem = raw.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    raw.with_file_name(format!("{}-master.{}", stem, format.extension()))
+}
+
+// Trim, filter, normalize and encode the WAV `source` into `output`
+pub fn make_master(source: &Path, output: &Path, spec: &MasterSpec) -> Result<(), String> {
+    let trim = match spec.trim_below_dbfs {
+        Some(threshold) => audible_range(source, threshold)?,
+        None => None,
+    };
+    pipeline::render(source, output, &spec.operations(trim), |_, _, _| {})?;
+    Ok(())
+}
+
+// Seconds from the first to the last frame peaking at or above `threshold_dbfs`,
+// padded; None when nothing is that loud
+fn audible_range(path: &Path, threshold_dbfs: f32) -> Result<Option<(f64, f64)>, String> {
+    let mut source = decode::open(path)?;
+    let info = source.info();
+    let channels = info.channels as usize;
+    let threshold = 10f32.powf(threshold_dbfs / 20.0);
+    let mut first = None;
+    let mut last = 0u64;
+    let mut position = 0u64;
+    let mut buffer = Vec::new();
+    loop {
+        let read = source.read_chunk(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        for (index, frame) in buffer.chunks_exact(channels).enumerate() {
+            if frame.iter().any(|sample| sample.abs() >= threshold) {
+                let frame = position + index as u64;
+                first.get_or_insert(frame);
+                last = frame;
+            }
+        }
+        position += read as u64;
+    }
+    let Some(first) = first else {
+        return Ok(None);
+    };
+    let rate = info.sample_rate as f64;
+    let pad = TRIM_PAD_MS as f64 / 1000.0;
+    let start_secs = (first as f64 / rate - pad).max(0.0);
+    let end_secs = ((last + 1) as f64 / rate + pad).min(position as f64 / rate);
+    Ok(Some((start_secs, end_secs)))
+}
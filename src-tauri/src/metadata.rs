@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::format::{BitrateMode, OutputFormat};
+use crate::wav::{self, WavStream};
+
+// WAV chunk with the channel labels, each one NUL-terminated UTF-8
+const LABELS_CHUNK: [u8; 4] = *b"chlb";
+const MAX_LABEL_CHARS: usize = 64;
+
+// Facts about how a recording was made, kept in a JSON file next to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RecordingMetadata {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub format: OutputFormat,
+    // How compressed output was encoded, and the bitrate it came out at
+    pub bitrate: Option<BitrateMode>,
+    pub average_kbps: Option<u32>,
+    pub started_at_unix_ms: u64,
+    // Audio dropped after the stream started, to skip driver start-up noise
+    pub discard_initial_ms: u64,
+    // One name per channel, e.g. "Host mic"
+    pub channel_labels: Option<Vec<String>>,
+    // Frames written per second of monotonic time while recording, and how
+    // far that is from `sample_rate` in parts per million
+    pub measured_sample_rate: Option<f64>,
+    pub clock_drift_ppm: Option<f64>,
+    // Pre-emphasis coefficient applied while recording, undone when read
+    pub emphasis: Option<f32>,
+    // Auto gain over the segment, continuing from the previous one's
+    pub gain_trajectory: Option<Vec<GainPoint>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GainPoint {
+    // From the start of the segment
+    pub at_secs: f64,
+    pub gain_db: f32,
+}
+
+impl Default for RecordingMetadata {
+    fn default() -> Self {
+        Self {
+            device: String::new(),
+            sample_rate: 0,
+            channels: 0,
+            format: OutputFormat::Wav,
+            bitrate: None,
+            average_kbps: None,
+            started_at_unix_ms: 0,
+            discard_initial_ms: 0,
+            channel_labels: None,
+            measured_sample_rate: None,
+            clock_drift_ppm: None,
+            emphasis: None,
+            gain_trajectory: None,
+        }
+    }
+}
+
+pub fn sidecar_path(audio: &Path) -> PathBuf {
+    audio.with_extension("json")
+}
+
+pub fn write(audio: &Path, metadata: &RecordingMetadata) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(metadata)
+        .map_err(|e| format!("Failed to serialize recording metadata: {}", e))?;
+    std::fs::write(sidecar_path(audio), json).map_err(|e| format!("Failed to write recording metadata: {}", e))
+}
+
+// Check labels for a recording with `channels` channels
+pub fn validate_channel_labels(labels: &[String], channels: u16) -> Result<(), String> {
+    if labels.len() != channels as usize {
+        return Err(format!("Got {} channel labels for {} channels", labels.len(), channels));
+    }
+    if labels.iter().any(|label| label.contains('\0') || label.chars().count() > MAX_LABEL_CHARS) {
+        return Err(format!("Channel labels must be at most {} characters with no NUL", MAX_LABEL_CHARS));
+    }
+    Ok(())
+}
+
+// Store the labels in the WAV itself so they travel with the file
+pub fn embed_channel_labels(path: &Path, labels: &[String]) -> Result<(), String> {
+    let data: Vec<u8> = labels.iter().flat_map(|label| label.bytes().chain([0])).collect();
+    wav::append_chunk(path, LABELS_CHUNK, &data)
+}
+
+fn embedded_channel_labels(path: &Path) -> Result<Option<Vec<String>>, String> {
+    Ok(wav::find_chunk(path, LABELS_CHUNK)?.map(|data| {
+        let mut labels: Vec<String> = data
+            .split(|&byte| byte == 0)
+            .map(|label| String::from_utf8_lossy(label).to_string())
+            .collect();
+        // Whatever follows the last terminator
+        labels.pop();
+        labels
+    }))
+}
+
+// A recording's sidecar metadata. Labels embedded in a WAV fill in when the
+// sidecar has none, or stand in for a sidecar that has gone missing.
+#[tauri::command]
+pub async fn read_recording_metadata(path: String) -> Result<RecordingMetadata, String> {
+    read(Path::new(&path))
+}
+
+pub fn read(path: &Path) -> Result<RecordingMetadata, String> {
+    let is_wav = OutputFormat::from_extension(
+        path.extension().and_then(|extension| extension.to_str()).unwrap_or_default(),
+    ) == Some(OutputFormat::Wav);
+    let mut metadata = match std::fs::read_to_string(sidecar_path(path)) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| format!("Failed to parse recording metadata: {}", e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && is_wav => {
+            let spec = WavStream::open(path)?.spec();
+            RecordingMetadata {
+                sample_rate: spec.sample_rate,
+                channels: spec.channels,
+                ..Default::default()
+            }
+        }
+        Err(e) => return Err(format!("Failed to read recording metadata: {}", e)),
+    };
+    if metadata.channel_labels.is_none() && is_wav {
+        metadata.channel_labels = embedded_channel_labels(path)?;
+    }
+    Ok(metadata)
+}
+
+// Pre-emphasis `path` was recorded with, which reading it should undo
+pub fn emphasis(path: &Path) -> Option<f32> {
+    read(path).ok().and_then(|metadata| metadata.emphasis)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleRateMeasurement {
+    pub nominal_rate: u32,
+    pub measured_rate: f64,
+    pub drift_ppm: f64,
+    // How far the audio slides against a true clock over an hour, positive
+    // when it runs long
+    pub drift_ms_per_hour: f64,
+}
+
+// The rate a recording's device actually delivered, from its metadata
+#[tauri::command]
+pub async fn measured_sample_rate(path: String) -> Result<SampleRateMeasurement, String> {
+    let metadata = read(Path::new(&path))?;
+    let (Some(measured_rate), Some(drift_ppm)) = (metadata.measured_sample_rate, metadata.clock_drift_ppm) else {
+        return Err("Recording has no sample rate measurement".to_string());
+    };
+    Ok(SampleRateMeasurement {
+        nominal_rate: metadata.sample_rate,
+        measured_rate,
+        drift_ppm,
+        drift_ms_per_hour: drift_ppm * 3.6,
+    })
+}
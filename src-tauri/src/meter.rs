@@ -0,0 +1,81 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::Serialize;
+
+use crate::capture;
+use crate::levels::ChannelLevels;
+
+const DEFAULT_METER_MS: u64 = 250;
+const MAX_METER_MS: u64 = 5000;
+const BAR_WIDTH: usize = 40;
+// Bars span this range; anything quieter renders as an empty bar
+const BAR_FLOOR_DBFS: f32 = -60.0;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MeterReading {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub frames: u64,
+    pub peak_dbfs: Vec<f32>,
+    pub rms_dbfs: Vec<f32>,
+    pub bars: Vec<String>,
+}
+
+// Sample the input briefly and report per-channel levels, so capture can be
+// checked without the frontend
+#[tauri::command]
+pub async fn meter_once(device_name: Option<String>, duration_ms: Option<u64>) -> Result<MeterReading, String> {
+    let duration_ms = duration_ms.unwrap_or(DEFAULT_METER_MS);
+    if duration_ms == 0 || duration_ms > MAX_METER_MS {
+        return Err(format!("Meter duration must be between 1 and {} ms", MAX_METER_MS));
+    }
+    sample_levels(device_name.as_deref(), Duration::from_millis(duration_ms))
+}
+
+pub fn sample_levels(device_name: Option<&str>, duration: Duration) -> Result<MeterReading, String> {
+    let device = capture::find_input_device(device_name)?;
+    let name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to get default input config: {}", e))?;
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+
+    let levels = Arc::new(Mutex::new(ChannelLevels::new(config.channels as usize)));
+    let levels_ref = levels.clone();
+    let stream = capture::build_f32_input_stream(&device, &config, sample_format, move |data| {
+        if let Ok(mut levels) = levels_ref.lock() {
+            levels.push_interleaved(data);
+        }
+    })?;
+    stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+    thread::sleep(duration);
+    drop(stream);
+
+    let levels = levels.lock().map_err(|e| e.to_string())?;
+    let peak_dbfs = levels.peak_dbfs();
+    let bars = peak_dbfs
+        .iter()
+        .enumerate()
+        .map(|(channel, &peak)| format!("ch{} [{}] {:>6.1} dBFS", channel + 1, render_bar(peak), peak))
+        .collect();
+
+    Ok(MeterReading {
+        device: name,
+        sample_rate: config.sample_rate.0,
+        channels: config.channels,
+        frames: levels.frames(),
+        peak_dbfs,
+        rms_dbfs: levels.rms_dbfs(),
+        bars,
+    })
+}
+
+fn render_bar(dbfs: f32) -> String {
+    let fraction = ((dbfs - BAR_FLOOR_DBFS) / -BAR_FLOOR_DBFS).clamp(0.0, 1.0);
+    let filled = (fraction * BAR_WIDTH as f32).round() as usize;
+    format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+}
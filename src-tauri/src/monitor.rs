@@ -0,0 +1,133 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::playback;
+use crate::RecordingState;
+
+pub const DEFAULT_MONITOR_GAIN: f32 = 1.0;
+// +12 dB; anything louder is asking for feedback
+const MAX_MONITOR_GAIN: f32 = 4.0;
+// Input queued beyond this is dropped so the monitor never lags far behind
+const MAX_LATENCY_MS: u64 = 100;
+// Output peaking this close to full scale for this long is taken as feedback
+const FEEDBACK_LEVEL: f32 = 0.9;
+const FEEDBACK_MS: u64 = 500;
+// Per-frame step towards a new gain, so changes don't click
+const GAIN_SMOOTHING: f32 = 0.002;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MonitorFeedbackEvent {
+    // The gain in use before the monitor was muted
+    gain: f32,
+}
+
+// Set how loud the monitor plays the input, as a linear gain. Only the
+// passthrough hears it; the recording keeps its own level. Returns the gain
+// after clamping.
+#[tauri::command]
+pub async fn set_monitor_gain(state: State<'_, RecordingState>, value: f32) -> Result<f32, String> {
+    if !value.is_finite() {
+        return Err("Monitor gain must be a number".to_string());
+    }
+    let value = value.clamp(0.0, MAX_MONITOR_GAIN);
+    *state.monitor_gain.lock().map_err(|e| e.to_string())? = value;
+    Ok(value)
+}
+
+// Input waiting to be played; filled from the input callback
+#[derive(Clone)]
+pub struct MonitorFeed {
+    queue: Arc<Mutex<VecDeque<f32>>>,
+    max_queued: usize,
+}
+
+impl MonitorFeed {
+    pub fn push(&self, data: &[f32]) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.extend(data);
+            let excess = queue.len().saturating_sub(self.max_queued);
+            queue.drain(..excess);
+        }
+    }
+}
+
+// Plays the input on the default output while recording
+pub struct Monitor {
+    feed: MonitorFeed,
+    _stream: cpal::Stream,
+}
+
+impl Monitor {
+    pub fn start(app_handle: AppHandle, channels: u16, sample_rate: u32, gain: Arc<Mutex<f32>>) -> Result<Self, String> {
+        let device = playback::find_output_device(None)?;
+        let supported = device
+            .supported_output_configs()
+            .map_err(|e| format!("Failed to get output configs: {}", e))?
+            .filter(|range| (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&sample_rate))
+            .max_by_key(|range| range.channels())
+            .ok_or_else(|| format!("Output can't play {} Hz; recording without monitoring", sample_rate))?
+            .with_sample_rate(cpal::SampleRate(sample_rate));
+        let sample_format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+        let output_channels = config.channels as usize;
+        let channels = channels as usize;
+
+        let feed = MonitorFeed {
+            queue: Arc::new(Mutex::new(VecDeque::new())),
+            max_queued: (MAX_LATENCY_MS * sample_rate as u64 / 1000) as usize * channels,
+        };
+        let queue = feed.queue.clone();
+        let feedback_frames = FEEDBACK_MS * sample_rate as u64 / 1000;
+        let mut hot_frames = 0u64;
+        let mut current = 0.0f32;
+        let mut frame_in = vec![0.0f32; channels];
+        let stream = playback::build_f32_output_stream(&device, &config, sample_format, move |data| {
+            let target = gain.lock().map(|gain| *gain).unwrap_or(0.0);
+            let Ok(mut queue) = queue.lock() else {
+                data.fill(0.0);
+                return;
+            };
+            let mut peak = 0.0f32;
+            for frame in data.chunks_mut(output_channels) {
+                current += (target - current) * GAIN_SMOOTHING;
+                if queue.len() >= channels {
+                    for sample in frame_in.iter_mut() {
+                        *sample = queue.pop_front().unwrap_or(0.0);
+                    }
+                } else {
+                    frame_in.fill(0.0);
+                }
+                for (index, sample) in frame.iter_mut().enumerate() {
+                    *sample = (frame_in[index % channels] * current).clamp(-1.0, 1.0);
+                    peak = peak.max(sample.abs());
+                }
+            }
+            drop(queue);
+
+            // A howl keeps the output pinned near full scale; mute rather
+            // than let it build
+            if peak >= FEEDBACK_LEVEL {
+                hot_frames += (data.len() / output_channels) as u64;
+            } else {
+                hot_frames = 0;
+            }
+            if hot_frames >= feedback_frames {
+                hot_frames = 0;
+                if let Ok(mut gain) = gain.lock() {
+                    let _ = app_handle.emit("monitor-feedback", MonitorFeedbackEvent { gain: *gain });
+                    *gain = 0.0;
+                }
+            }
+        })?;
+        stream.play().map_err(|e| format!("Failed to start output stream: {}", e))?;
+        Ok(Self { feed, _stream: stream })
+    }
+
+    pub fn feed(&self) -> MonitorFeed {
+        self.feed.clone()
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+// Highest mode accepted: permission bits plus setuid, setgid and sticky
+const MAX_MODE: u32 = 0o7777;
+
+// Applied to every file a recording leaves behind once it is finished,
+// instead of whatever the umask gave them. On Windows files take their
+// access from the folder they are in: only the owner write bit of `mode`
+// means anything there (clear makes the file read-only), and an owner can't
+// be set at all.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FilePermissions {
+    // Mode bits, e.g. 0o640 for owner read/write and group read
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+impl FilePermissions {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mode.is_some_and(|mode| mode > MAX_MODE) {
+            return Err(format!("File mode must be at most {:o}", MAX_MODE));
+        }
+        if cfg!(not(unix)) && (self.uid.is_some() || self.gid.is_some()) {
+            return Err("File ownership can only be set on Unix".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, path: &Path) -> Result<(), String> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = self.mode {
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                    .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))?;
+            }
+            if self.uid.is_some() || self.gid.is_some() {
+                std::os::unix::fs::chown(path, self.uid, self.gid)
+                    .map_err(|e| format!("Failed to set owner of {}: {}", path.display(), e))?;
+            }
+        }
+        #[cfg(not(unix))]
+        if self.mode.is_some_and(|mode| mode & 0o200 == 0) {
+            let mut permissions = std::fs::metadata(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+                .permissions();
+            permissions.set_readonly(true);
+            std::fs::set_permissions(path, permissions)
+                .map_err(|e| format!("Failed to set permissions on {}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,329 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::convert;
+use crate::decode::{self, SourceInfo};
+use crate::dsp::{Biquad, FilterChain, Resampler};
+use crate::encode;
+use crate::eq::EqCurve;
+use crate::format::{BitrateMode, OutputFormat};
+use crate::levels;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const MIN_SAMPLE_RATE: u32 = 8_000;
+const MAX_SAMPLE_RATE: u32 = 384_000;
+const MAX_GAIN_DB: f32 = 60.0;
+const BUTTERWORTH_Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum Operation {
+    // Keep `start_secs..end_secs` of the input (to the end when open)
+    Trim { start_secs: f64, end_secs: Option<f64> },
+    Gain { db: f32 },
+    Highpass { cutoff_hz: f32, q: Option<f32> },
+    // The bands sit next to `op`, as in {"op": "eq", "lowShelf": {..}}
+    Eq(EqCurve),
+    // Scale so the peak lands on `target_dbfs`
+    Normalize { target_dbfs: f32 },
+    Resample { sample_rate: u32 },
+    Encode { format: OutputFormat, bitrate: Option<BitrateMode> },
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PipelineSpec {
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PipelineProgress {
+    input: String,
+    // "analyze" while measuring for a normalize step, then "render"
+    pass: &'static str,
+    processed_secs: f64,
+    total_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineResult {
+    pub path: String,
+    pub format: OutputFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_secs: f64,
+    // Gain the normalize step applied, if there was one
+    pub normalize_gain_db: Option<f32>,
+}
+
+// One step of the chain after trimming, working on interleaved buffers
+enum Stage {
+    Gain(f32),
+    Filter(FilterChain),
+    Resample(Resampler),
+}
+
+impl Stage {
+    fn process(&mut self, buffer: &mut Vec<f32>, scratch: &mut Vec<f32>) {
+        match self {
+            Stage::Gain(gain) => buffer.iter_mut().for_each(|sample| *sample *= *gain),
+            Stage::Filter(filter) => filter.process_interleaved(buffer),
+            Stage::Resample(resampler) => {
+                resampler.process_interleaved(buffer, scratch);
+                std::mem::swap(buffer, scratch);
+            }
+        }
+    }
+}
+
+// Trim, gain, filter, EQ, normalize, resample and encode `input` into `output`
+// in the order given. Everything is written in one pass; a normalize step
+// first reads the input once to measure the peak it will see.
+#[tauri::command]
+pub async fn process(
+    app_handle: tauri::AppHandle,
+    input: String,
+    output: String,
+    spec: PipelineSpec,
+) -> Result<PipelineResult, String> {
+    render(Path::new(&input), Path::new(&output), &spec.operations, |pass, processed_secs, total_secs| {
+        let _ = app_handle.emit(
+            "process-progress",
+            PipelineProgress {
+                input: input.clone(),
+                pass,
+                processed_secs,
+                total_secs,
+            },
+        );
+    })
+}
+
+// The pipeline itself; `progress` gets the pass, seconds processed and the
+// total when it is known
+pub fn render(
+    input_path: &Path,
+    output_path: &Path,
+    operations: &[Operation],
+    progress: impl Fn(&'static str, f64, Option<f64>),
+) -> Result<PipelineResult, String> {
+    let (encode_format, bitrate) = validate_order(operations)?;
+    let format = convert::resolve_output(input_path, output_path, encode_format)?;
+    if let Some(mode) = bitrate {
+        mode.validate(format)?;
+    }
+
+    let info = decode::open(input_path)?.info();
+    let (start_frame, end_frame) = trim_range(operations, info.sample_rate)?;
+    let total_secs = info
+        .frames
+        .map(|frames| (end_frame.unwrap_or(frames).min(frames).saturating_sub(start_frame)) as f64)
+        .map(|frames| frames / info.sample_rate as f64);
+    let report = |pass: &'static str, frames: u64| progress(pass, frames as f64 / info.sample_rate as f64, total_secs);
+
+    // Everything before a normalize step runs once without writing to find
+    // the peak it has to scale
+    let mut normalize_gain_db = None;
+    let (mut stages, output_rate, placeholder) = build_stages(operations, &info)?;
+    let normalize = operations.iter().enumerate().find_map(|(index, operation)| match operation {
+        Operation::Normalize { target_dbfs } => Some((index, *target_dbfs)),
+        _ => None,
+    });
+    if let (Some((index, target_dbfs)), Some(stage_index)) = (normalize, placeholder) {
+        let (mut before, _, _) = build_stages(&operations[..index], &info)?;
+        let mut peak = 0.0f32;
+        run(
+            input_path,
+            start_frame,
+            end_frame,
+            &mut before,
+            |data| {
+                peak = data.iter().fold(peak, |peak, sample| peak.max(sample.abs()));
+                Ok(())
+            },
+            |frames| report("analyze", frames),
+        )?;
+        let gain_db = if peak > 0.0 { target_dbfs - levels::amplitude_to_dbfs(peak) } else { 0.0 };
+        normalize_gain_db = Some(gain_db);
+        stages[stage_index] = Stage::Gain(10f32.powf(gain_db / 20.0));
+    }
+
+    let mut sink = encode::create_sink(
+        output_path,
+        format,
+        convert::output_spec(&info, info.channels, output_rate),
+        bitrate,
+    )?;
+    let mut written = 0u64;
+    let result = run(
+        input_path,
+        start_frame,
+        end_frame,
+        &mut stages,
+        |data| {
+            written += (data.len() / info.channels as usize) as u64;
+            sink.write(data)
+        },
+        |frames| report("render", frames),
+    )
+    .and_then(|_| sink.finalize());
+    // Don't leave a truncated file behind
+    match result {
+        Ok(()) if written == 0 => Err("Nothing left to write after trimming".to_string()),
+        other => other,
+    }
+    .inspect_err(|_| {
+        let _ = std::fs::remove_file(output_path);
+    })?;
+    convert::copy_tags(input_path, output_path)?;
+
+    Ok(PipelineResult {
+        path: output_path.to_string_lossy().to_string(),
+        format,
+        sample_rate: output_rate,
+        channels: info.channels,
+        duration_secs: written as f64 / output_rate as f64,
+        normalize_gain_db,
+    })
+}
+
+// Check where each operation may appear; returns the encode step's settings
+pub fn validate_order(operations: &[Operation]) -> Result<(Option<OutputFormat>, Option<BitrateMode>), String> {
+    let last = operations.len().saturating_sub(1);
+    let mut seen_normalize = false;
+    let mut encode = (None, None);
+    for (index, operation) in operations.iter().enumerate() {
+        match operation {
+            Operation::Trim { .. } if index != 0 => {
+                return Err("Trim must be the first operation".to_string());
+            }
+            Operation::Encode { .. } if index != last => {
+                return Err("Encode must be the last operation".to_string());
+            }
+            Operation::Normalize { .. } if seen_normalize => {
+                return Err("Only one normalize operation is allowed".to_string());
+            }
+            Operation::Normalize { target_dbfs } => {
+                if !(*target_dbfs <= 0.0 && *target_dbfs >= levels::MIN_DBFS) {
+                    return Err(format!("Normalize target must be between {} and 0 dBFS", levels::MIN_DBFS));
+                }
+                seen_normalize = true;
+            }
+            Operation::Gain { db } if !(-MAX_GAIN_DB..=MAX_GAIN_DB).contains(db) => {
+                return Err(format!("Gain must be within ±{} dB", MAX_GAIN_DB));
+            }
+            Operation::Resample { sample_rate } if !(MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(sample_rate) => {
+                return Err(format!(
+                    "Sample rate must be between {} and {} Hz",
+                    MIN_SAMPLE_RATE, MAX_SAMPLE_RATE
+                ));
+            }
+            Operation::Encode { format, bitrate } => encode = (Some(*format), *bitrate),
+            _ => {}
+        }
+    }
+    Ok(encode)
+}
+
+// Trim bounds in input frames
+fn trim_range(operations: &[Operation], sample_rate: u32) -> Result<(u64, Option<u64>), String> {
+    match operations.first() {
+        Some(Operation::Trim { start_secs, end_secs }) => {
+            if !(*start_secs >= 0.0 && end_secs.is_none_or(|end| end > *start_secs)) {
+                return Err("Trim range must start at or after 0 and end after it starts".to_string());
+            }
+            let rate = sample_rate as f64;
+            Ok(((start_secs * rate).round() as u64, end_secs.map(|end| (end * rate).round() as u64)))
+        }
+        _ => Ok((0, None)),
+    }
+}
+
+// Stages for everything but trim and encode, tracking the rate through any
+// resampling; normalize is a unity-gain placeholder until it is measured.
+// Returns the rate coming out of the last stage and where the placeholder is,
+// which isn't the operation's index since trim, encode and a resample to the
+// same rate add no stage.
+fn build_stages(operations: &[Operation], info: &SourceInfo) -> Result<(Vec<Stage>, u32, Option<usize>), String> {
+    let channels = info.channels as usize;
+    let mut rate = info.sample_rate;
+    let mut stages = Vec::new();
+    let mut placeholder = None;
+    for operation in operations {
+        match *operation {
+            Operation::Trim { .. } | Operation::Encode { .. } => {}
+            Operation::Gain { db } => stages.push(Stage::Gain(10f32.powf(db / 20.0))),
+            Operation::Normalize { .. } => {
+                placeholder = Some(stages.len());
+                stages.push(Stage::Gain(1.0));
+            }
+            Operation::Highpass { cutoff_hz, q } => {
+                if !(cutoff_hz > 0.0 && cutoff_hz < rate as f32 / 2.0) {
+                    return Err(format!("Highpass cutoff must be between 0 and {} Hz", rate / 2));
+                }
+                let q = q.unwrap_or(BUTTERWORTH_Q);
+                if !(q > 0.0 && q.is_finite()) {
+                    return Err("Highpass Q must be positive".to_string());
+                }
+                stages.push(Stage::Filter(FilterChain::new(&[Biquad::highpass(rate, cutoff_hz, q)], channels)));
+            }
+            Operation::Eq(ref curve) => stages.push(Stage::Filter(curve.build(rate, channels)?)),
+            Operation::Resample { sample_rate } => {
+                if sample_rate == rate {
+                    continue;
+                }
+                stages.push(Stage::Resample(Resampler::new(rate, sample_rate, channels)));
+                rate = sample_rate;
+            }
+        }
+    }
+    Ok((stages, rate, placeholder))
+}
+
+// Decode `input`, drop frames outside the trim range, run the rest through
+// `stages` and hand each processed buffer to `consume`
+fn run(
+    input: &Path,
+    start_frame: u64,
+    end_frame: Option<u64>,
+    stages: &mut [Stage],
+    mut consume: impl FnMut(&[f32]) -> Result<(), String>,
+    mut progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let mut source = decode::open(input)?;
+    let channels = source.info().channels as usize;
+    let mut buffer = Vec::new();
+    let mut scratch = Vec::new();
+    let mut position = 0u64;
+    let mut last_progress = Instant::now();
+    loop {
+        let read = source.read_chunk(&mut buffer)? as u64;
+        if read == 0 {
+            break;
+        }
+        let chunk_start = position;
+        position += read;
+        let keep_from = start_frame.saturating_sub(chunk_start).min(read);
+        let keep_to = end_frame.map_or(read, |end| end.saturating_sub(chunk_start).min(read));
+        if keep_from < keep_to {
+            buffer.truncate(keep_to as usize * channels);
+            buffer.drain(..keep_from as usize * channels);
+            for stage in stages.iter_mut() {
+                stage.process(&mut buffer, &mut scratch);
+            }
+            consume(&buffer)?;
+        }
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            progress(position.saturating_sub(start_frame));
+        }
+        if end_frame.is_some_and(|end| position >= end) {
+            break;
+        }
+    }
+    Ok(())
+}
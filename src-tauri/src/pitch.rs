@@ -0,0 +1,229 @@
+use std::path::Path;
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::Serialize;
+
+use crate::dsp::Emphasis;
+use crate::metadata;
+use crate::wav::WavStream;
+
+const WINDOW_SECS: f64 = 0.1;
+const MIN_FREQUENCY_HZ: f64 = 40.0;
+const MAX_FREQUENCY_HZ: f64 = 2_000.0;
+// Peaks within this fraction of the highest one count as the period, which
+// keeps the detector from jumping an octave down
+const PEAK_THRESHOLD: f64 = 0.9;
+// Below this the window is treated as noise or silence
+const MIN_CLARITY: f64 = 0.7;
+const SILENCE_RMS: f64 = 1e-4;
+const NOTE_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PitchReading {
+    pub at_secs: f64,
+    // None when the window has no clear pitch
+    pub frequency_hz: Option<f64>,
+    pub note: Option<String>,
+    // Offset from the nearest equal-tempered note (A4 = 440 Hz)
+    pub cents: Option<f64>,
+    pub clarity: f64,
+}
+
+impl PitchReading {
+    fn new(at_secs: f64, detected: Option<(f64, f64)>) -> Self {
+        let clarity = detected.map(|(_, clarity)| clarity).unwrap_or(0.0);
+        match detected.filter(|&(_, clarity)| clarity >= MIN_CLARITY) {
+            Some((frequency, _)) => {
+                let midi = 69.0 + 12.0 * (frequency / 440.0).log2();
+                let nearest = midi.round();
+                let index = nearest as i64;
+                let name = format!("{}{}", NOTE_NAMES[index.rem_euclid(12) as usize], index.div_euclid(12) - 1);
+                Self {
+                    at_secs,
+                    frequency_hz: Some(frequency),
+                    note: Some(name),
+                    cents: Some((midi - nearest) * 100.0),
+                    clarity,
+                }
+            }
+            None => Self {
+                at_secs,
+                frequency_hz: None,
+                note: None,
+                cents: None,
+                clarity,
+            },
+        }
+    }
+}
+
+// Pitch of the window starting at `at_secs`
+#[tauri::command]
+pub async fn detect_pitch(path: String, at_secs: f64) -> Result<PitchReading, String> {
+    let mut stream = WavStream::open(Path::new(&path))?;
+    let spec = stream.spec();
+    let start = (at_secs.max(0.0) * spec.sample_rate as f64) as u32;
+    if start >= stream.frames() {
+        return Err("Position is past the end of the recording".to_string());
+    }
+    stream.seek(start)?;
+    let window = read_mono(&mut stream, window_frames(spec.sample_rate), metadata::emphasis(Path::new(&path)))?;
+    let mut detector = PitchDetector::new(window.len());
+    Ok(PitchReading::new(at_secs, detector.detect(&window, spec.sample_rate)))
+}
+
+// Pitch every `hop_ms` over the whole file; compiled in with `pitch-track`
+#[tauri::command]
+pub async fn pitch_track(path: String, hop_ms: Option<u64>) -> Result<Vec<PitchReading>, String> {
+    track::pitch_track(Path::new(&path), hop_ms.unwrap_or(50))
+}
+
+fn window_frames(sample_rate: u32) -> usize {
+    (sample_rate as f64 * WINDOW_SECS) as usize
+}
+
+// Up to `frames` frames from the stream, averaged down to mono, with the
+// recording's pre-emphasis undone
+fn read_mono(stream: &mut WavStream, frames: usize, emphasis: Option<f32>) -> Result<Vec<f32>, String> {
+    let channels = stream.spec().channels as usize;
+    let mut buffer = Vec::new();
+    stream.read_chunk(frames, &mut buffer)?;
+    if let Some(coefficient) = emphasis {
+        Emphasis::de(coefficient, channels).process_interleaved(&mut buffer);
+    }
+    Ok(buffer
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect())
+}
+
+// McLeod pitch method: normalized square difference function, with the
+// autocorrelation done by FFT
+struct PitchDetector {
+    size: usize,
+    forward: std::sync::Arc<dyn rustfft::Fft<f64>>,
+    inverse: std::sync::Arc<dyn rustfft::Fft<f64>>,
+    spectrum: Vec<Complex<f64>>,
+}
+
+impl PitchDetector {
+    fn new(window: usize) -> Self {
+        let size = (window * 2).next_power_of_two();
+        let mut planner = FftPlanner::new();
+        Self {
+            size,
+            forward: planner.plan_fft_forward(size),
+            inverse: planner.plan_fft_inverse(size),
+            spectrum: vec![Complex::new(0.0, 0.0); size],
+        }
+    }
+
+    // Fundamental frequency and clarity (0..1) of `samples`
+    fn detect(&mut self, samples: &[f32], sample_rate: u32) -> Option<(f64, f64)> {
+        let len = samples.len().min(self.size / 2);
+        let samples = &samples[..len];
+        let rms = (samples.iter().map(|&s| (s as f64).powi(2)).sum::<f64>() / len.max(1) as f64).sqrt();
+        if rms < SILENCE_RMS {
+            return None;
+        }
+
+        self.spectrum.fill(Complex::new(0.0, 0.0));
+        for (bin, &sample) in self.spectrum.iter_mut().zip(samples) {
+            bin.re = sample as f64;
+        }
+        self.forward.process(&mut self.spectrum);
+        for bin in self.spectrum.iter_mut() {
+            *bin = Complex::new(bin.norm_sqr(), 0.0);
+        }
+        self.inverse.process(&mut self.spectrum);
+        let autocorrelation: Vec<f64> = self.spectrum[..len].iter().map(|bin| bin.re / self.size as f64).collect();
+
+        let min_lag = (sample_rate as f64 / MAX_FREQUENCY_HZ) as usize;
+        let max_lag = ((sample_rate as f64 / MIN_FREQUENCY_HZ) as usize).min(len - 1);
+        if min_lag + 2 >= max_lag {
+            return None;
+        }
+
+        // m(τ) = Σ x[j]² + x[j+τ]², updated incrementally as τ grows
+        let mut energy = 2.0 * autocorrelation[0];
+        let mut nsdf = vec![0.0; max_lag + 1];
+        for lag in 0..=max_lag {
+            if lag > 0 {
+                let dropped = samples[lag - 1] as f64;
+                let end = samples[len - lag] as f64;
+                energy -= dropped * dropped + end * end;
+            }
+            nsdf[lag] = if energy > 0.0 { 2.0 * autocorrelation[lag] / energy } else { 0.0 };
+        }
+
+        // Key maxima: the highest point of each positive lobe
+        let mut peaks = Vec::new();
+        let mut lag = nsdf.iter().position(|&value| value < 0.0)?;
+        while lag < max_lag {
+            while lag < max_lag && nsdf[lag] <= 0.0 {
+                lag += 1;
+            }
+            let mut best = lag;
+            while lag < max_lag && nsdf[lag] > 0.0 {
+                if nsdf[lag] > nsdf[best] {
+                    best = lag;
+                }
+                lag += 1;
+            }
+            if best >= min_lag && best < max_lag && nsdf[best] > 0.0 {
+                peaks.push(best);
+            }
+        }
+        let highest = peaks.iter().map(|&lag| nsdf[lag]).fold(0.0, f64::max);
+        let period = *peaks.iter().find(|&&lag| nsdf[lag] >= highest * PEAK_THRESHOLD)?;
+
+        // Parabolic interpolation around the peak for sub-sample precision
+        let (a, b, c) = (nsdf[period - 1], nsdf[period], nsdf[period + 1]);
+        let denominator = a - 2.0 * b + c;
+        let shift = if denominator.abs() > f64::EPSILON { 0.5 * (a - c) / denominator } else { 0.0 };
+        let clarity = (b - 0.25 * (a - c) * shift).min(1.0);
+        Some((sample_rate as f64 / (period as f64 + shift), clarity))
+    }
+}
+
+#[cfg(not(feature = "pitch-track"))]
+mod track {
+    use std::path::Path;
+    use super::PitchReading;
+
+    pub fn pitch_track(_path: &Path, _hop_ms: u64) -> Result<Vec<PitchReading>, String> {
+        Err("Pitch tracking is not compiled into this build".to_string())
+    }
+}
+
+#[cfg(feature = "pitch-track")]
+mod track {
+    use std::path::Path;
+    use super::{read_mono, window_frames, PitchDetector, PitchReading};
+    use crate::metadata;
+    use crate::wav::WavStream;
+
+    pub fn pitch_track(path: &Path, hop_ms: u64) -> Result<Vec<PitchReading>, String> {
+        if hop_ms == 0 {
+            return Err("Hop must be at least 1 ms".to_string());
+        }
+        let mut stream = WavStream::open(path)?;
+        let emphasis = metadata::emphasis(path);
+        let sample_rate = stream.spec().sample_rate;
+        let window = window_frames(sample_rate);
+        let hop = ((sample_rate as u64 * hop_ms / 1000) as u32).max(1);
+        let mut detector = PitchDetector::new(window);
+
+        let mut readings = Vec::new();
+        let mut start = 0u32;
+        while start as usize + window <= stream.frames() as usize {
+            stream.seek(start)?;
+            let samples = read_mono(&mut stream, window, emphasis)?;
+            let at_secs = start as f64 / sample_rate as f64;
+            readings.push(PitchReading::new(at_secs, detector.detect(&samples, sample_rate)));
+            start += hop;
+        }
+        Ok(readings)
+    }
+}
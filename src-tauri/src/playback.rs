@@ -0,0 +1,63 @@
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::{FromSample, SizedSample};
+
+// Find an output device by name, or the host default when no name is given
+pub fn find_output_device(name: Option<&str>) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    match name {
+        Some(name) => host
+            .output_devices()
+            .map_err(|e| format!("Failed to enumerate output devices: {}", e))?
+            .find(|device| device.name().map(|n| n == name).unwrap_or(false))
+            .ok_or_else(|| format!("Output device not found: {}", name)),
+        None => host
+            .default_output_device()
+            .ok_or_else(|| "No output device available".to_string()),
+    }
+}
+
+// Build an output stream that asks `fill` for interleaved f32 samples and
+// converts them to the device's native sample format
+pub fn build_f32_output_stream<F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+    fill: F,
+) -> Result<cpal::Stream, String>
+where
+    F: FnMut(&mut [f32]) + Send + 'static,
+{
+    match sample_format {
+        cpal::SampleFormat::F32 => build_stream::<f32, F>(device, config, fill),
+        cpal::SampleFormat::I16 => build_stream::<i16, F>(device, config, fill),
+        cpal::SampleFormat::U16 => build_stream::<u16, F>(device, config, fill),
+        _ => Err("Unsupported sample format".to_string()),
+    }
+}
+
+fn build_stream<T, F>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut fill: F,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+    F: FnMut(&mut [f32]) + Send + 'static,
+{
+    let mut buffer: Vec<f32> = Vec::new();
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                buffer.clear();
+                buffer.resize(data.len(), 0.0);
+                fill(&mut buffer);
+                for (out, &sample) in data.iter_mut().zip(buffer.iter()) {
+                    *out = T::from_sample(sample);
+                }
+            },
+            |err| eprintln!("Stream error: {}", err),
+            None,
+        )
+        .map_err(|e| format!("Failed to build output stream: {}", e))
+}
@@ -0,0 +1,232 @@
+use cpal::traits::DeviceTrait;
+use serde::Serialize;
+
+use crate::capture;
+use crate::dsp::{self, ChannelMixer};
+use crate::format::{OutputFormat, QualityPreset};
+use crate::levels;
+use crate::metadata;
+use crate::recorder;
+use crate::RecordingOptions;
+use crate::{DEFAULT_RECONNECT_TIMEOUT_SECS, MAX_AUTO_GAIN_DB, MAX_DISCARD_MS, MAX_POST_ROLL_MS, MAX_RECONNECT_TIMEOUT_SECS};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingCheck {
+    // Name of the option as the frontend sends it
+    pub setting: &'static str,
+    pub valid: bool,
+    pub error: Option<String>,
+    // Nearest value that would work, when there is one
+    pub suggestion: Option<serde_json::Value>,
+}
+
+impl SettingCheck {
+    fn new(setting: &'static str, result: Result<(), String>) -> Self {
+        Self {
+            setting,
+            valid: result.is_ok(),
+            error: result.err(),
+            suggestion: None,
+        }
+    }
+
+    fn suggest(mut self, value: impl Serialize) -> Self {
+        if !self.valid {
+            self.suggestion = serde_json::to_value(value).ok().filter(|value| !value.is_null());
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordCheck {
+    // Every check passed
+    pub ok: bool,
+    // The input start_recording would use
+    pub device: Option<String>,
+    pub checks: Vec<SettingCheck>,
+}
+
+// The checks start_recording makes on its options before touching a device
+pub fn option_checks(options: &RecordingOptions) -> Vec<SettingCheck> {
+    let discard_ms = options.discard_initial_ms.unwrap_or(0);
+    let post_roll_ms = options.stop_post_roll_ms.unwrap_or(0);
+    let reconnect_secs = options.reconnect_timeout_secs.unwrap_or(DEFAULT_RECONNECT_TIMEOUT_SECS);
+    let limit = |ok: bool, error: String| if ok { Ok(()) } else { Err(error) };
+    vec![
+        SettingCheck::new(
+            "discardInitialMs",
+            limit(discard_ms <= MAX_DISCARD_MS, format!("Initial discard can be at most {} ms", MAX_DISCARD_MS)),
+        )
+        .suggest(MAX_DISCARD_MS),
+        SettingCheck::new(
+            "bufferFrames",
+            limit(options.buffer_frames != Some(0), "Buffer size must be at least 1 frame".to_string()),
+        )
+        .suggest(1),
+        SettingCheck::new(
+            "stopPostRollMs",
+            limit(post_roll_ms <= MAX_POST_ROLL_MS, format!("Stop post-roll can be at most {} ms", MAX_POST_ROLL_MS)),
+        )
+        .suggest(MAX_POST_ROLL_MS),
+        SettingCheck::new(
+            "autoGain",
+            options.auto_gain.map_or(Ok(()), |settings| {
+                if !(settings.target_dbfs >= levels::MIN_DBFS && settings.target_dbfs <= 0.0) {
+                    return Err(format!("Auto gain target must be between {} and 0 dBFS", levels::MIN_DBFS));
+                }
+                if !(0.0..=MAX_AUTO_GAIN_DB).contains(&settings.max_gain_db) {
+                    return Err(format!("Auto gain can add at most {} dB", MAX_AUTO_GAIN_DB));
+                }
+                Ok(())
+            }),
+        ),
+        SettingCheck::new("autoMaster", options.auto_master.as_ref().map_or(Ok(()), |spec| spec.validate())),
+        SettingCheck::new("preEmphasis", options.pre_emphasis.map_or(Ok(()), dsp::validate_emphasis)),
+        SettingCheck::new("filters", options.filters.validate()),
+        SettingCheck::new("lowSpace", options.low_space.map_or(Ok(()), |policy| policy.validate())),
+        SettingCheck::new(
+            "filePermissions",
+            options.file_permissions.map_or(Ok(()), |permissions| permissions.validate()),
+        ),
+        SettingCheck::new(
+            "reconnectTimeoutSecs",
+            limit(
+                (1..=MAX_RECONNECT_TIMEOUT_SECS).contains(&reconnect_secs),
+                format!("Reconnect timeout must be between 1 and {} seconds", MAX_RECONNECT_TIMEOUT_SECS),
+            ),
+        )
+        .suggest(reconnect_secs.clamp(1, MAX_RECONNECT_TIMEOUT_SECS)),
+    ]
+}
+
+// Whether start_recording would accept `config` on `device_name` (or the
+// input it would pick itself), setting by setting, with the nearest values
+// that would work in place of those that don't. Nothing is opened, so the
+// settings UI can call this on every change.
+#[tauri::command]
+pub async fn can_record(device_name: Option<String>, config: Option<RecordingOptions>) -> Result<RecordCheck, String> {
+    let options = config.unwrap_or_default();
+    let mut checks = Vec::new();
+
+    // With auto_format the format is only chosen once recording starts
+    let format = options
+        .auto_format
+        .is_none()
+        .then(|| options.format.unwrap_or(options.preset.unwrap_or(QualityPreset::Lossless).format()));
+    if let Some(format) = format {
+        checks.push(SettingCheck::new("format", format.ensure_available()).suggest(OutputFormat::Wav));
+        if let Some(mode) = options.bitrate {
+            checks.push(SettingCheck::new("bitrate", mode.validate(format)));
+        }
+    }
+    checks.extend(option_checks(&options));
+
+    // Like start_recording, the first listed device that is connected and
+    // fits the settings, or else the first that is connected
+    let names = match device_name {
+        Some(name) => vec![name],
+        None => options.device_priority.clone(),
+    };
+    let candidates: Vec<cpal::Device> = if names.is_empty() {
+        capture::find_default_input_device(options.device_role.unwrap_or_default())
+            .into_iter()
+            .collect()
+    } else {
+        names
+            .iter()
+            .filter_map(|name| capture::find_input_device(Some(name)).ok())
+            .collect()
+    };
+    let mut chosen = None;
+    for device in candidates {
+        let device_checks = device_checks(&device, &options, format);
+        let fits = device_checks.iter().all(|check| check.valid);
+        if chosen.is_none() || fits {
+            chosen = Some((device, device_checks));
+        }
+        if fits {
+            break;
+        }
+    }
+    let device = match chosen {
+        Some((device, device_checks)) => {
+            checks.extend(device_checks);
+            device.name().ok()
+        }
+        None => {
+            let error = if names.is_empty() {
+                "No input device available".to_string()
+            } else {
+                format!("None of {} is connected", names.join(", "))
+            };
+            let fallback = capture::find_default_input_device(options.device_role.unwrap_or_default())
+                .ok()
+                .and_then(|device| device.name().ok());
+            checks.push(SettingCheck::new("device", Err(error)).suggest(fallback));
+            None
+        }
+    };
+
+    Ok(RecordCheck {
+        ok: checks.iter().all(|check| check.valid),
+        device,
+        checks,
+    })
+}
+
+// Rate, buffer and channel checks start_recording makes once it has a device
+fn device_checks(device: &cpal::Device, options: &RecordingOptions, format: Option<OutputFormat>) -> Vec<SettingCheck> {
+    let request = options.sample_rate;
+    let supported = match capture::select_input_config(
+        device,
+        request.map(|request| request.rate),
+        request.is_some_and(|request| request.nearest_rate),
+    ) {
+        Ok(supported) => supported,
+        Err(e) => {
+            // The closest rate below the request, or the device's own
+            let nearest = request
+                .and_then(|request| capture::select_input_config(device, Some(request.rate), true).ok())
+                .or_else(|| device.default_input_config().ok())
+                .map(|supported| supported.sample_rate().0);
+            return vec![SettingCheck::new("sampleRate", Err(e)).suggest(nearest)];
+        }
+    };
+    let mut checks = vec![SettingCheck::new("sampleRate", Ok(()))];
+
+    let buffer_range = match *supported.buffer_size() {
+        cpal::SupportedBufferSize::Range { min, max } => Some((min, max)),
+        cpal::SupportedBufferSize::Unknown => None,
+    };
+    checks.push(
+        SettingCheck::new("bufferFrames", recorder::check_buffer_frames(&supported, options.buffer_frames))
+            .suggest(options.buffer_frames.zip(buffer_range).map(|(frames, (min, max))| frames.clamp(min, max))),
+    );
+
+    checks.extend(channel_checks(supported.channels(), options, format));
+    checks
+}
+
+// Mixing, MP3 and label checks for an input with `input_channels`
+pub fn channel_checks(input_channels: u16, options: &RecordingOptions, format: Option<OutputFormat>) -> Vec<SettingCheck> {
+    let mut checks = Vec::new();
+    let channels = options.output_channels.unwrap_or(input_channels);
+    let max_channels = if format == Some(OutputFormat::Mp3) { 2 } else { u16::MAX };
+    let channel_result = (channels != input_channels)
+        .then(|| ChannelMixer::new(input_channels, channels))
+        .transpose()
+        .and_then(|_| {
+            if channels > max_channels {
+                return Err("MP3 output supports at most 2 channels".to_string());
+            }
+            Ok(())
+        });
+    checks.push(SettingCheck::new("outputChannels", channel_result).suggest(input_channels.min(max_channels)));
+    if let Some(labels) = options.channel_labels.as_ref() {
+        checks.push(SettingCheck::new("channelLabels", metadata::validate_channel_labels(labels, channels)));
+    }
+    checks
+}
@@ -0,0 +1,274 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use serde::Serialize;
+
+// How much of the start and end of an Ogg file is searched for pages
+const OGG_SCAN_BYTES: u64 = 64 * 1024;
+const OPUS_GRANULE_RATE: f64 = 48_000.0;
+
+// Layer III bitrates in kbps by header index, for MPEG-1 and MPEG-2/2.5
+const MP3_BITRATES_V1: [u32; 15] = [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320];
+const MP3_BITRATES_V2: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamInfo {
+    pub codec: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_secs: f64,
+    // Bitrate the encoder was asked for, when the header records one
+    pub nominal_kbps: Option<u32>,
+    pub variable: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitrateReport {
+    pub codec: String,
+    pub file_bytes: u64,
+    pub duration_secs: f64,
+    pub average_kbps: f64,
+    pub nominal_kbps: Option<u32>,
+    pub variable: bool,
+}
+
+// Average bitrate of a compressed recording from its size and duration
+#[tauri::command]
+pub async fn audio_bitrate(path: String) -> Result<BitrateReport, String> {
+    let path = Path::new(&path);
+    let info = probe(path)?;
+    let file_bytes = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata: {}", e))?
+        .len();
+    if info.duration_secs <= 0.0 {
+        return Err("Recording has no audio".to_string());
+    }
+    Ok(BitrateReport {
+        codec: info.codec,
+        file_bytes,
+        duration_secs: info.duration_secs,
+        average_kbps: file_bytes as f64 * 8.0 / info.duration_secs / 1000.0,
+        nominal_kbps: info.nominal_kbps,
+        variable: info.variable,
+    })
+}
+
+// Identify a compressed file by its magic bytes and read its stream info
+pub fn probe(path: &Path) -> Result<StreamInfo, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open audio file: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut magic = [0u8; 4];
+    reader
+        .read_exact(&mut magic)
+        .map_err(|e| format!("Failed to read audio file: {}", e))?;
+    reader.rewind().map_err(|e| format!("Failed to read audio file: {}", e))?;
+
+    match &magic {
+        b"RIFF" | b"FORM" => Err("File is uncompressed PCM; its bitrate is sample rate × bit depth × channels".to_string()),
+        b"fLaC" => probe_flac(&mut reader),
+        b"OggS" => probe_ogg(&mut reader),
+        [b'I', b'D', b'3', _] => probe_mp3(&mut reader),
+        [0xFF, second, _, _] if second & 0xE0 == 0xE0 => probe_mp3(&mut reader),
+        _ => Err("Unrecognized audio format".to_string()),
+    }
+}
+
+fn read_error(e: std::io::Error) -> String {
+    format!("Failed to read audio file: {}", e)
+}
+
+struct Mp3Frame {
+    length: u64,
+    bitrate_kbps: u32,
+    sample_rate: u32,
+    samples: u32,
+    channels: u16,
+    // Offset of the Xing/Info tag within the frame
+    side_info_end: usize,
+}
+
+fn parse_mp3_header(header: [u8; 4]) -> Option<Mp3Frame> {
+    if header[0] != 0xFF || header[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+    let version = (header[1] >> 3) & 0b11;
+    let layer = (header[1] >> 1) & 0b11;
+    let bitrate_index = (header[2] >> 4) as usize;
+    let rate_index = ((header[2] >> 2) & 0b11) as usize;
+    let padding = ((header[2] >> 1) & 1) as u64;
+    let mono = header[3] >> 6 == 0b11;
+    // Only Layer III, which is all the encoder writes
+    if version == 0b01 || layer != 0b01 || bitrate_index == 0 || bitrate_index == 15 || rate_index == 3 {
+        return None;
+    }
+
+    let mpeg1 = version == 0b11;
+    let bitrate_kbps = if mpeg1 { MP3_BITRATES_V1 } else { MP3_BITRATES_V2 }[bitrate_index];
+    let base_rate = [44_100, 48_000, 32_000][rate_index];
+    let sample_rate = match version {
+        0b11 => base_rate,
+        0b10 => base_rate / 2,
+        _ => base_rate / 4,
+    };
+    let (samples, coefficient) = if mpeg1 { (1152, 144) } else { (576, 72) };
+    let side_info = match (mpeg1, mono) {
+        (true, false) => 32,
+        (true, true) => 17,
+        (false, false) => 17,
+        (false, true) => 9,
+    };
+    Some(Mp3Frame {
+        length: coefficient * bitrate_kbps as u64 * 1000 / sample_rate as u64 + padding,
+        bitrate_kbps,
+        sample_rate,
+        samples,
+        channels: if mono { 1 } else { 2 },
+        side_info_end: 4 + side_info,
+    })
+}
+
+// Walk every frame header, since VBR files have no fixed frame size
+fn probe_mp3<R: Read + Seek>(reader: &mut R) -> Result<StreamInfo, String> {
+    let mut id3 = [0u8; 10];
+    reader.read_exact(&mut id3).map_err(read_error)?;
+    let mut offset = 0u64;
+    if &id3[..3] == b"ID3" {
+        let size = id3[6..10].iter().fold(0u64, |size, &byte| (size << 7) | (byte & 0x7F) as u64);
+        let footer = if id3[5] & 0x10 != 0 { 10 } else { 0 };
+        offset = 10 + size + footer;
+    }
+
+    let mut total_samples = 0u64;
+    let mut first: Option<(u32, u16)> = None;
+    let mut bitrates = Vec::new();
+    let mut tagged_vbr = false;
+    let mut frame = vec![0u8; 64];
+    loop {
+        reader.seek(SeekFrom::Start(offset)).map_err(read_error)?;
+        let mut header = [0u8; 4];
+        if reader.read_exact(&mut header).is_err() {
+            break;
+        }
+        // Anything that isn't a frame (an ID3v1 tag, trailing junk) ends the stream
+        let Some(parsed) = parse_mp3_header(header) else {
+            break;
+        };
+
+        if first.is_none() {
+            first = Some((parsed.sample_rate, parsed.channels));
+            // A Xing/Info tag frame describes the stream but carries no audio
+            frame.resize(parsed.side_info_end + 4, 0);
+            reader.seek(SeekFrom::Start(offset)).map_err(read_error)?;
+            if reader.read_exact(&mut frame).is_ok() {
+                let tag = &frame[parsed.side_info_end..];
+                if tag == b"Xing" || tag == b"Info" {
+                    tagged_vbr = tag == b"Xing";
+                    offset += parsed.length;
+                    continue;
+                }
+            }
+        }
+        total_samples += parsed.samples as u64;
+        bitrates.push(parsed.bitrate_kbps);
+        offset += parsed.length;
+    }
+
+    let (sample_rate, channels) = first.ok_or_else(|| "No MP3 frames found".to_string())?;
+    let variable = tagged_vbr || bitrates.windows(2).any(|pair| pair[0] != pair[1]);
+    Ok(StreamInfo {
+        codec: "mp3".to_string(),
+        sample_rate,
+        channels,
+        duration_secs: total_samples as f64 / sample_rate as f64,
+        nominal_kbps: if variable { None } else { bitrates.first().copied() },
+        variable,
+    })
+}
+
+// Duration comes straight from STREAMINFO; FLAC has no target bitrate
+fn probe_flac<R: Read + Seek>(reader: &mut R) -> Result<StreamInfo, String> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic).map_err(read_error)?;
+    let mut header = [0u8; 4];
+    reader.read_exact(&mut header).map_err(read_error)?;
+    if header[0] & 0x7F != 0 {
+        return Err("FLAC file does not start with STREAMINFO".to_string());
+    }
+    let mut info = [0u8; 34];
+    reader.read_exact(&mut info).map_err(read_error)?;
+
+    let sample_rate = ((info[10] as u32) << 12) | ((info[11] as u32) << 4) | (info[12] as u32 >> 4);
+    let channels = ((info[12] >> 1) & 0b111) as u16 + 1;
+    let total_samples = (((info[13] & 0x0F) as u64) << 32) | u32::from_be_bytes([info[14], info[15], info[16], info[17]]) as u64;
+    if sample_rate == 0 {
+        return Err("FLAC STREAMINFO has no sample rate".to_string());
+    }
+    Ok(StreamInfo {
+        codec: "flac".to_string(),
+        sample_rate,
+        channels,
+        duration_secs: total_samples as f64 / sample_rate as f64,
+        nominal_kbps: None,
+        variable: true,
+    })
+}
+
+// Opus or Vorbis in Ogg: the identification header gives the rate, the last
+// page's granule position gives the length
+fn probe_ogg<R: Read + Seek>(reader: &mut R) -> Result<StreamInfo, String> {
+    let len = reader.seek(SeekFrom::End(0)).map_err(read_error)?;
+    reader.rewind().map_err(read_error)?;
+    let mut head = Vec::new();
+    reader.by_ref().take(OGG_SCAN_BYTES).read_to_end(&mut head).map_err(read_error)?;
+
+    // First page: 27-byte header, segment table, then the first packet
+    if head.len() < 27 {
+        return Err("Ogg file is truncated".to_string());
+    }
+    let packet_start = 27 + head[26] as usize;
+    let packet = head.get(packet_start..).unwrap_or_default();
+
+    let tail_start = len.saturating_sub(OGG_SCAN_BYTES);
+    reader.seek(SeekFrom::Start(tail_start)).map_err(read_error)?;
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail).map_err(read_error)?;
+    let last_page = tail
+        .windows(4)
+        .rposition(|window| window == b"OggS")
+        .filter(|&page| page + 14 <= tail.len())
+        .ok_or_else(|| "No Ogg pages found at end of file".to_string())?;
+    let granule = u64::from_le_bytes(tail[last_page + 6..last_page + 14].try_into().unwrap_or_default());
+
+    if packet.len() >= 19 && packet.starts_with(b"OpusHead") {
+        let channels = packet[9] as u16;
+        let pre_skip = u16::from_le_bytes([packet[10], packet[11]]) as u64;
+        let sample_rate = u32::from_le_bytes([packet[12], packet[13], packet[14], packet[15]]);
+        Ok(StreamInfo {
+            codec: "opus".to_string(),
+            sample_rate,
+            channels,
+            duration_secs: granule.saturating_sub(pre_skip) as f64 / OPUS_GRANULE_RATE,
+            nominal_kbps: None,
+            variable: true,
+        })
+    } else if packet.len() >= 30 && packet.starts_with(b"\x01vorbis") {
+        let channels = packet[11] as u16;
+        let sample_rate = u32::from_le_bytes([packet[12], packet[13], packet[14], packet[15]]);
+        let nominal = i32::from_le_bytes([packet[20], packet[21], packet[22], packet[23]]);
+        if sample_rate == 0 {
+            return Err("Vorbis header has no sample rate".to_string());
+        }
+        Ok(StreamInfo {
+            codec: "vorbis".to_string(),
+            sample_rate,
+            channels,
+            duration_secs: granule as f64 / sample_rate as f64,
+            nominal_kbps: (nominal > 0).then_some(nominal as u32 / 1000),
+            variable: true,
+        })
+    } else {
+        Err("Unsupported Ogg codec".to_string())
+    }
+}
@@ -0,0 +1,240 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::capture;
+use crate::decode;
+use crate::dsp::Emphasis;
+use crate::metadata;
+use crate::playback;
+use crate::wav::{WavSink, WavStream};
+use crate::RecordingState;
+
+const POLL_MS: u64 = 20;
+const MAX_PRE_ROLL_SECS: f64 = 30.0;
+// Slack on top of the expected duration before giving up on the input
+const TIMEOUT_SLACK_SECS: f64 = 5.0;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PunchOptions {
+    // Play the base from this long before the punch-in so the take lines up
+    pub pre_roll_secs: f64,
+    pub crossfade_ms: f64,
+    // Play the base on the default output while recording
+    pub monitor: bool,
+}
+
+impl Default for PunchOptions {
+    fn default() -> Self {
+        Self {
+            pre_roll_secs: 2.0,
+            crossfade_ms: 10.0,
+            monitor: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PunchResult {
+    pub path: String,
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub captured_frames: usize,
+}
+
+// Record new input over `start_secs..end_secs` of `base_path`, writing the
+// base with that range replaced (and cross-faded at both ends) to `output`
+#[tauri::command]
+pub async fn punch_record(
+    state: State<'_, RecordingState>,
+    base_path: String,
+    start_secs: f64,
+    end_secs: f64,
+    output: String,
+    options: Option<PunchOptions>,
+) -> Result<PunchResult, String> {
+    let options = options.unwrap_or_default();
+    if *state.is_recording.lock().map_err(|e| e.to_string())? {
+        return Err("Cannot punch in while recording".to_string());
+    }
+
+    let base = Path::new(&base_path);
+    if base == Path::new(&output) {
+        return Err("Output must be a different file from the base recording".to_string());
+    }
+    let stream = WavStream::open(base)?;
+    let spec = stream.spec();
+    let base_frames = stream.frames() as usize;
+    drop(stream);
+    let rate = spec.sample_rate as f64;
+    let channels = spec.channels as usize;
+    if !(start_secs >= 0.0 && start_secs < end_secs) {
+        return Err("Punch range must start at or after 0 and end after it starts".to_string());
+    }
+    let start = (start_secs * rate).round() as usize;
+    let end = (end_secs * rate).round() as usize;
+    if end > base_frames {
+        return Err(format!(
+            "Punch range ends past the end of the base recording ({:.2} s)",
+            base_frames as f64 / rate
+        ));
+    }
+    if !(0.0..=MAX_PRE_ROLL_SECS).contains(&options.pre_roll_secs) {
+        return Err(format!("Pre-roll must be between 0 and {} seconds", MAX_PRE_ROLL_SECS));
+    }
+    if options.crossfade_ms < 0.0 {
+        return Err("Cross-fade length can't be negative".to_string());
+    }
+    let take_frames = end - start;
+    let fade_frames = ((options.crossfade_ms / 1000.0 * rate) as usize).min(take_frames / 2);
+
+    // The take must line up sample for sample with the base
+    let device = capture::find_input_device(None)?;
+    let supported = capture::select_input_config(&device, Some(spec.sample_rate), false)?;
+    if supported.channels() != spec.channels {
+        return Err(format!(
+            "Input has {} channels but the base recording has {}",
+            supported.channels(),
+            spec.channels
+        ));
+    }
+    let sample_format = supported.sample_format();
+    let input_config: cpal::StreamConfig = supported.into();
+
+    // Frames of the base handed to the output so far, counted from the pre-roll
+    let played = Arc::new(AtomicU64::new(0));
+    let pre_roll_start = start.saturating_sub((options.pre_roll_secs * rate) as usize);
+    let punch_in_at = if options.monitor { (start - pre_roll_start) as u64 } else { 0 };
+    let monitor = if options.monitor {
+        Some(start_monitor(base, pre_roll_start, end, channels, spec.sample_rate, played.clone())?)
+    } else {
+        None
+    };
+
+    let needed = take_frames * channels;
+    let take = Arc::new(Mutex::new(Vec::with_capacity(needed)));
+    let take_ref = take.clone();
+    let played_ref = played.clone();
+    let input = capture::build_f32_input_stream(&device, &input_config, sample_format, move |data| {
+        if played_ref.load(Ordering::Relaxed) < punch_in_at {
+            return;
+        }
+        if let Ok(mut take) = take_ref.lock() {
+            let room = needed - take.len();
+            take.extend_from_slice(&data[..data.len().min(room)]);
+        }
+    })?;
+    input.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+
+    let timeout = Duration::from_secs_f64((end - pre_roll_start) as f64 / rate + TIMEOUT_SLACK_SECS);
+    let started = Instant::now();
+    loop {
+        thread::sleep(Duration::from_millis(POLL_MS));
+        if take.lock().map_err(|e| e.to_string())?.len() >= needed {
+            break;
+        }
+        if started.elapsed() > timeout {
+            return Err("Input stopped delivering audio before the punch range was filled".to_string());
+        }
+    }
+    drop(input);
+    if let Some(monitor) = monitor {
+        drop(monitor);
+    }
+
+    let take = std::mem::take(&mut *take.lock().map_err(|e| e.to_string())?);
+    splice(base, Path::new(&output), start, &take, fade_frames)?;
+
+    Ok(PunchResult {
+        path: output,
+        start_secs: start as f64 / rate,
+        end_secs: end as f64 / rate,
+        captured_frames: take_frames,
+    })
+}
+
+// Play `from..to` of the base on the default output, counting frames played.
+// Output latency isn't compensated; the count runs slightly ahead of the sound.
+fn start_monitor(
+    base: &Path,
+    from: usize,
+    to: usize,
+    channels: usize,
+    sample_rate: u32,
+    played: Arc<AtomicU64>,
+) -> Result<cpal::Stream, String> {
+    let device = playback::find_output_device(None)?;
+    let supported = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to get default output config: {}", e))?;
+    if supported.sample_rate().0 != sample_rate {
+        return Err(format!(
+            "Output runs at {} Hz but the base recording is {} Hz; punch in without monitoring",
+            supported.sample_rate().0,
+            sample_rate
+        ));
+    }
+    let sample_format = supported.sample_format();
+    let config: cpal::StreamConfig = supported.into();
+    let output_channels = config.channels as usize;
+
+    let mut stream = WavStream::open(base)?;
+    stream.seek(from as u32)?;
+    let mut audio = Vec::new();
+    stream.read_chunk(to - from, &mut audio)?;
+    // Heard flat, like every other read of the recording
+    if let Some(coefficient) = metadata::emphasis(base) {
+        Emphasis::de(coefficient, channels).process_interleaved(&mut audio);
+    }
+
+    let output = playback::build_f32_output_stream(&device, &config, sample_format, move |data| {
+        for frame in data.chunks_mut(output_channels) {
+            let position = played.fetch_add(1, Ordering::Relaxed) as usize;
+            let source = audio.get(position * channels..(position + 1) * channels);
+            for (index, sample) in frame.iter_mut().enumerate() {
+                *sample = source.map(|source| source[index % channels]).unwrap_or(0.0);
+            }
+        }
+    })?;
+    output.play().map_err(|e| format!("Failed to start output stream: {}", e))?;
+    Ok(output)
+}
+
+// Copy the base to `output`, replacing frames from `start` with `take` and
+// cross-fading over `fade_frames` at each edge. The base is read flat to
+// match the take, so the output is flat too.
+fn splice(base: &Path, output: &Path, start: usize, take: &[f32], fade_frames: usize) -> Result<(), String> {
+    let spec = WavStream::open(base)?.spec();
+    let mut source = decode::open(base)?;
+    let channels = spec.channels as usize;
+    let take_frames = take.len() / channels;
+    let mut sink = WavSink::create(output, spec)?;
+    let mut buffer = Vec::new();
+    let mut frame_index = 0usize;
+    while source.read_chunk(&mut buffer)? > 0 {
+        for frame in buffer.chunks_exact_mut(channels) {
+            if let Some(offset) = frame_index.checked_sub(start).filter(|&offset| offset < take_frames) {
+                let from_edge = offset.min(take_frames - 1 - offset);
+                let gain = if from_edge < fade_frames {
+                    (from_edge as f32 + 0.5) / fade_frames as f32
+                } else {
+                    1.0
+                };
+                let new = &take[offset * channels..(offset + 1) * channels];
+                for (sample, &new) in frame.iter_mut().zip(new) {
+                    *sample = *sample * (1.0 - gain) + new * gain;
+                }
+            }
+            frame_index += 1;
+        }
+        sink.write(&buffer)?;
+    }
+    sink.finalize()
+}
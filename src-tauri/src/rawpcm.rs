@@ -0,0 +1,294 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::convert;
+use crate::format::OutputFormat;
+
+// Nothing in the samples gives the rate away, so it is assumed unless hinted
+const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+const CANDIDATE_CHANNELS: [u16; 2] = [1, 2];
+const MAX_CHANNELS: u16 = 32;
+// Keeps the header's byte rate within its 32 bits
+const MAX_SAMPLE_RATE: u32 = 768_000;
+// How much of the file is looked at when guessing
+const SAMPLE_BYTES: usize = 1 << 20;
+const COPY_BYTES: usize = 1 << 16;
+// Mean step between successive samples relative to their mean magnitude for
+// white noise; real audio is far smoother than this
+const NOISE_ROUGHNESS: f64 = std::f64::consts::SQRT_2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PcmEncoding {
+    // Unsigned 8-bit, as WAV stores it
+    U8,
+    S16,
+    S24,
+    S32,
+    F32,
+}
+
+impl PcmEncoding {
+    const ALL: [PcmEncoding; 5] = [
+        PcmEncoding::S16,
+        PcmEncoding::S24,
+        PcmEncoding::S32,
+        PcmEncoding::F32,
+        PcmEncoding::U8,
+    ];
+
+    fn bytes(self) -> usize {
+        match self {
+            PcmEncoding::U8 => 1,
+            PcmEncoding::S16 => 2,
+            PcmEncoding::S24 => 3,
+            PcmEncoding::S32 | PcmEncoding::F32 => 4,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        let mut sample = [0u8; 4];
+        sample[..bytes.len()].copy_from_slice(bytes);
+        if big_endian {
+            sample[..bytes.len()].reverse();
+        }
+        match self {
+            PcmEncoding::U8 => (sample[0] as f64 - 128.0) / 128.0,
+            PcmEncoding::S16 => i16::from_le_bytes([sample[0], sample[1]]) as f64 / 32_768.0,
+            // Shift up so the sign bit lands in place, then back down
+            PcmEncoding::S24 => (i32::from_le_bytes([0, sample[0], sample[1], sample[2]]) >> 8) as f64 / 8_388_608.0,
+            PcmEncoding::S32 => i32::from_le_bytes(sample) as f64 / 2_147_483_648.0,
+            PcmEncoding::F32 => f32::from_le_bytes(sample) as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PcmParams {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub encoding: PcmEncoding,
+    pub big_endian: bool,
+}
+
+impl PcmParams {
+    fn block_align(&self) -> usize {
+        self.channels as usize * self.encoding.bytes()
+    }
+
+    fn validate(&self, file_bytes: u64) -> Result<(), String> {
+        if !(1..=MAX_CHANNELS).contains(&self.channels) {
+            return Err(format!("Channel count must be between 1 and {}", MAX_CHANNELS));
+        }
+        if !(1..=MAX_SAMPLE_RATE).contains(&self.sample_rate) {
+            return Err(format!("Sample rate must be between 1 and {} Hz", MAX_SAMPLE_RATE));
+        }
+        if !file_bytes.is_multiple_of(self.block_align() as u64) {
+            return Err(format!(
+                "File size ({} bytes) isn't a whole number of {}-byte frames",
+                file_bytes,
+                self.block_align()
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Anything already known about the file; the rest is guessed
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PcmHints {
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    pub encoding: Option<PcmEncoding>,
+    pub big_endian: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PcmGuess {
+    pub params: PcmParams,
+    // 0 (looks like noise) to 1 (very smooth, so very likely right)
+    pub confidence: f64,
+    pub duration_secs: f64,
+}
+
+// Work out how a headerless PCM file is probably laid out, by trying every
+// layout that divides its size cleanly and scoring how much like audio the
+// samples look under each. Best guess first.
+#[tauri::command]
+pub async fn guess_pcm_params(path: String, hints: Option<PcmHints>) -> Result<Vec<PcmGuess>, String> {
+    let hints = hints.unwrap_or_default();
+    let path = Path::new(&path);
+    let file_bytes = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .len();
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(SAMPLE_BYTES as u64).read_to_end(&mut head))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    if head.is_empty() {
+        return Err("File is empty".to_string());
+    }
+
+    let sample_rate = hints.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+    let channels = match hints.channels {
+        Some(channels) => vec![channels],
+        None => CANDIDATE_CHANNELS.to_vec(),
+    };
+    let encodings = match hints.encoding {
+        Some(encoding) => vec![encoding],
+        None => PcmEncoding::ALL.to_vec(),
+    };
+    let endians = match hints.big_endian {
+        Some(big_endian) => vec![big_endian],
+        None => vec![false, true],
+    };
+
+    let mut guesses = Vec::new();
+    for &encoding in &encodings {
+        // Byte order means nothing for single bytes
+        let endians = if encoding.bytes() == 1 { &[false][..] } else { &endians[..] };
+        for &big_endian in endians {
+            for &channels in &channels {
+                let params = PcmParams {
+                    sample_rate,
+                    channels,
+                    encoding,
+                    big_endian,
+                };
+                if params.validate(file_bytes).is_err() {
+                    continue;
+                }
+                guesses.push(PcmGuess {
+                    params,
+                    confidence: confidence(&head, &params),
+                    duration_secs: (file_bytes / params.block_align() as u64) as f64 / sample_rate as f64,
+                });
+            }
+        }
+    }
+    if guesses.is_empty() {
+        return Err("No sample layout divides the file size cleanly".to_string());
+    }
+    guesses.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    Ok(guesses)
+}
+
+// How smooth each channel is when `data` is read with `params`
+fn confidence(data: &[u8], params: &PcmParams) -> f64 {
+    let width = params.encoding.bytes();
+    let channels = params.channels as usize;
+    let mut steps = vec![0.0f64; channels];
+    let mut levels = vec![0.0f64; channels];
+    let mut previous = vec![0.0f64; channels];
+    let mut frames = 0usize;
+    let mut invalid = 0usize;
+    for frame in data.chunks_exact(params.block_align()) {
+        for (channel, bytes) in frame.chunks_exact(width).enumerate() {
+            let mut sample = params.encoding.decode(bytes, params.big_endian);
+            // Floats read from the wrong bytes are often huge or not numbers
+            if !(sample.is_finite() && sample.abs() <= 2.0) {
+                invalid += 1;
+                sample = 0.0;
+            }
+            if frames > 0 {
+                steps[channel] += (sample - previous[channel]).abs();
+            }
+            levels[channel] += sample.abs();
+            previous[channel] = sample;
+        }
+        frames += 1;
+    }
+    if frames < 2 || invalid * 100 > frames * channels {
+        return 0.0;
+    }
+    let roughness: Vec<f64> = steps
+        .iter()
+        .zip(&levels)
+        .filter(|(_, &level)| level > 0.0)
+        .map(|(step, level)| step / level)
+        .collect();
+    if roughness.is_empty() {
+        return 0.0;
+    }
+    let mean = roughness.iter().sum::<f64>() / roughness.len() as f64;
+    (1.0 - mean / NOISE_ROUGHNESS).clamp(0.0, 1.0)
+}
+
+// Put a WAV header on a raw PCM file so ordinary players can open it
+#[tauri::command]
+pub async fn wrap_pcm_as_wav(path: String, params: PcmParams, output: String) -> Result<String, String> {
+    let input = Path::new(&path);
+    let output_path = Path::new(&output);
+    // Compares the files themselves, so `./a.raw` or a link to it is caught
+    convert::resolve_output(input, output_path, Some(OutputFormat::Wav))?;
+    let data_bytes = std::fs::metadata(input)
+        .map_err(|e| format!("Failed to read file: {}", e))?
+        .len();
+    params.validate(data_bytes)?;
+    let data_size = u32::try_from(data_bytes)
+        .ok()
+        .filter(|size| *size <= u32::MAX - 36)
+        .ok_or("File is too large for a WAV")?;
+
+    let width = params.encoding.bytes();
+    let block_align = params.block_align() as u16;
+    let format_tag: u16 = if params.encoding == PcmEncoding::F32 { 3 } else { 1 };
+    let mut header = Vec::with_capacity(44);
+    header.extend_from_slice(b"RIFF");
+    header.extend_from_slice(&(36 + data_size + data_size % 2).to_le_bytes());
+    header.extend_from_slice(b"WAVEfmt ");
+    header.extend_from_slice(&16u32.to_le_bytes());
+    header.extend_from_slice(&format_tag.to_le_bytes());
+    header.extend_from_slice(&params.channels.to_le_bytes());
+    header.extend_from_slice(&params.sample_rate.to_le_bytes());
+    header.extend_from_slice(&(params.sample_rate * block_align as u32).to_le_bytes());
+    header.extend_from_slice(&block_align.to_le_bytes());
+    header.extend_from_slice(&(width as u16 * 8).to_le_bytes());
+    header.extend_from_slice(b"data");
+    header.extend_from_slice(&data_size.to_le_bytes());
+
+    let io_error = |e: std::io::Error| format!("Failed to write WAV file: {}", e);
+    let mut reader = BufReader::new(File::open(input).map_err(|e| format!("Failed to read file: {}", e))?);
+    let mut writer = BufWriter::new(File::create(output_path).map_err(io_error)?);
+    let result = (|| {
+        writer.write_all(&header)?;
+        // A whole number of samples per read so byte swapping never splits one
+        let mut buffer = vec![0u8; COPY_BYTES / width * width];
+        loop {
+            let read = read_full(&mut reader, &mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            let chunk = &mut buffer[..read];
+            if params.big_endian {
+                chunk.chunks_exact_mut(width).for_each(|sample| sample.reverse());
+            }
+            writer.write_all(chunk)?;
+        }
+        if data_size % 2 == 1 {
+            writer.write_all(&[0])?;
+        }
+        writer.flush()
+    })();
+    result.map_err(io_error).inspect_err(|_| {
+        let _ = std::fs::remove_file(output_path);
+    })?;
+    Ok(output)
+}
+
+// Read until `buffer` is full or the input ends
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
@@ -0,0 +1,1419 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::capture::{self, DeviceRole};
+use crate::dsp::{AutoGain, ChannelMixer, Emphasis, Resampler};
+use crate::disk;
+use crate::encode;
+use crate::filters::{FilterSettings, FilterToggles, LiveFilters};
+use crate::format::{self, BitrateMode, LowSpacePolicy, OutputFormat, QualityPreset};
+use crate::frames::{self, FrameStream, FrameTap};
+use crate::keepalive::{InputConsumer, KeptInput, StreamKey};
+use crate::master::{self, MasterEvent, MasterSpec};
+use crate::metadata::{self, GainPoint, RecordingMetadata};
+use crate::monitor::Monitor;
+use crate::permissions::FilePermissions;
+use crate::session::{self, Session};
+use crate::wav::{self, WavSink};
+
+// No callbacks for this long while recording means the device has gone
+const STALL_MS: u64 = 3_000;
+const RECONNECT_POLL_MS: u64 = 500;
+const LOW_SPACE_CHECK_SECS: u64 = 30;
+
+// Wall-clock intervals that split boundaries can be aligned to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SegmentAlign {
+    Minute,
+    QuarterHour,
+    HalfHour,
+    Hour,
+}
+
+impl SegmentAlign {
+    pub fn interval_secs(self) -> u64 {
+        match self {
+            SegmentAlign::Minute => 60,
+            SegmentAlign::QuarterHour => 15 * 60,
+            SegmentAlign::HalfHour => 30 * 60,
+            SegmentAlign::Hour => 60 * 60,
+        }
+    }
+}
+
+// First boundary after `now` on the local clock, so hourly segments start on
+// the hour even in zones with a half-hour UTC offset
+fn next_boundary(now: SystemTime, interval_secs: u64) -> SystemTime {
+    let offset_ms = chrono::Local::now().offset().local_minus_utc() as i64 * 1000;
+    let now_ms = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64;
+    let interval_ms = interval_secs as i64 * 1000;
+    let next_local_ms = ((now_ms + offset_ms).div_euclid(interval_ms) + 1) * interval_ms;
+    UNIX_EPOCH + Duration::from_millis((next_local_ms - offset_ms) as u64)
+}
+
+pub fn unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+// Path of a segment: `<base>.<ext>`, or `<base>-NNN.<ext>` when splitting
+pub fn segment_path(base: &Path, index: Option<usize>, extension: &str) -> PathBuf {
+    let stem = base.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match index {
+        Some(index) => format!("{}-{:03}.{}", stem, index, extension),
+        None => format!("{}.{}", stem, extension),
+    };
+    base.with_file_name(name)
+}
+
+pub struct RecorderConfig {
+    // Output path without extension
+    pub base_path: PathBuf,
+    pub device_role: DeviceRole,
+    // Devices to try by name, in order, instead of the role's default
+    pub device_priority: Vec<String>,
+    pub output_format: OutputFormat,
+    pub bitrate: Option<BitrateMode>,
+    // Step down to smaller output when free space runs low; changes
+    // output_format and bitrate as the recording goes
+    pub low_space: Option<LowSpacePolicy>,
+    pub segment_align: Option<SegmentAlign>,
+    // Live level/PCM frames sent to the frontend over an IPC channel
+    pub frame_stream: Option<FrameStream>,
+    // High-rate UI updates are skipped while this is false
+    pub window_visible: Arc<Mutex<bool>>,
+    pub sample_rate: Option<SampleRateRequest>,
+    // Drop this much audio after the stream starts
+    pub discard_initial_ms: u64,
+    pub buffer_frames: Option<u32>,
+    // Capture continues this long after stop is requested
+    pub stop_post_roll: Duration,
+    // Up- or down-mix to this many channels instead of keeping the input's
+    pub output_channels: Option<u16>,
+    pub auto_gain: Option<AutoGainSettings>,
+    pub channel_labels: Option<Vec<String>>,
+    // First-order pre-emphasis coefficient applied before writing
+    pub pre_emphasis: Option<f32>,
+    pub filters: FilterSettings,
+    // Which filter stages are on, flipped by the frontend while recording
+    pub filter_toggles: Arc<FilterToggles>,
+    pub auto_master: Option<MasterSpec>,
+    // Play the input back at this gain while recording
+    pub monitor_gain: Option<Arc<Mutex<f32>>>,
+    // Mode and owner given to each finished file
+    pub file_permissions: Option<FilePermissions>,
+    // Stream left open between recordings, shared with the app state
+    pub kept_input: Arc<Mutex<Option<KeptInput>>>,
+    pub session: Arc<Mutex<Option<Session>>>,
+    // Leave the stream open after this recording, closing it once it has
+    // been idle this long
+    pub keep_alive_idle: Option<Duration>,
+    // Wait this long for a lost device to come back instead of ending the
+    // recording; None leaves the recording running without it as before
+    pub reconnect_timeout: Option<Duration>,
+    // Device the frontend asked to move to, picked up between polls
+    pub device_switch: Arc<Mutex<Option<String>>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SampleRateRequest {
+    pub rate: u32,
+    // Fall back to the closest supported rate below `rate` instead of failing
+    pub nearest_rate: bool,
+    // After falling back, resample so the file still has the requested rate
+    pub resample: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoGainSettings {
+    // RMS level the gain aims for
+    pub target_dbfs: f32,
+    // The most the gain may add, so silence isn't pulled up into hiss
+    pub max_gain_db: f32,
+}
+
+impl Default for AutoGainSettings {
+    fn default() -> Self {
+        Self {
+            target_dbfs: -20.0,
+            max_gain_db: 20.0,
+        }
+    }
+}
+
+impl RecorderConfig {
+    pub fn first_output_path(&self) -> PathBuf {
+        let index = self.segment_align.map(|_| 1);
+        segment_path(&self.base_path, index, self.output_format.extension())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigAdjustedEvent {
+    pub requested_rate: u32,
+    pub device_rate: u32,
+    pub output_rate: u32,
+    pub resampled: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceAttempt {
+    pub device: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSelectedEvent {
+    pub device: String,
+    // Position in device_priority
+    pub priority: usize,
+    // Devices ahead of it that couldn't be used, and why
+    pub skipped: Vec<DeviceAttempt>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceLostEvent {
+    pub device: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectedEvent {
+    pub device: String,
+    pub offline_ms: u64,
+    // The segment recording resumed into
+    pub index: usize,
+    pub path: String,
+}
+
+// Recording stopped on its own, without stop_recording
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingEndedEvent {
+    pub reason: String,
+    // The last file written, if the recording got that far
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSwitchedEvent {
+    pub from: String,
+    pub to: String,
+    // The segment the new device records into
+    pub index: usize,
+    pub path: String,
+    // The new device runs at a different rate or channel count and is
+    // converted to the session's
+    pub converted: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSwitchFailedEvent {
+    pub device: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatDowngradedEvent {
+    pub from: OutputFormat,
+    pub preset: QualityPreset,
+    pub format: OutputFormat,
+    pub free_bytes: u64,
+    // The first segment in the new format, now being written; the one just
+    // closed keeps `from`
+    pub index: usize,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitEvent {
+    pub index: usize,
+    pub boundary_unix_ms: u64,
+    pub previous_path: String,
+    pub path: String,
+}
+
+// Clock-aligned splitting state; boundaries are counted in frames so that no
+// sample is lost or duplicated between segments
+struct SplitSchedule {
+    interval: Duration,
+    segment_frames: u64,
+    next_boundary: SystemTime,
+}
+
+// Frames written into a segment against the monotonic clock, to measure the
+// rate the device really ran at
+#[derive(Default)]
+struct SegmentClock {
+    started: Option<Instant>,
+    latest: Option<Instant>,
+    // Frames written before the latest buffer arrived
+    frames_before_latest: u64,
+    frames: u64,
+}
+
+// Shorter spans say more about callback jitter than about the clock
+const MIN_CLOCK_SECS: f64 = 1.0;
+// Spacing of the auto gain points kept in each segment's metadata
+const GAIN_POINT_SECS: f64 = 1.0;
+
+impl SegmentClock {
+    fn record(&mut self, now: Instant, frames: u64) {
+        self.started.get_or_insert(now);
+        if self.latest != Some(now) {
+            self.latest = Some(now);
+            self.frames_before_latest = self.frames;
+        }
+        self.frames += frames;
+    }
+
+    fn measured_rate(&self) -> Option<f64> {
+        let elapsed = self.latest?.duration_since(self.started?).as_secs_f64();
+        (elapsed >= MIN_CLOCK_SECS).then(|| self.frames_before_latest as f64 / elapsed)
+    }
+}
+
+// A segment that has been written out, waiting to be encoded and announced
+struct FinishedSegment {
+    wav_path: PathBuf,
+    // The boundary it ended on and the index of the segment started there
+    boundary: SystemTime,
+    next_index: usize,
+    measured_rate: Option<f64>,
+    gain_trajectory: Vec<GainPoint>,
+}
+
+// Owns the WAV file currently being written and rotates it at split
+// boundaries. Runs inside the audio callback, so finished segments are only
+// queued here and announced from the recording thread.
+struct SegmentWriter {
+    base_path: PathBuf,
+    spec: hound::WavSpec,
+    sink: Option<WavSink>,
+    index: usize,
+    frames_left: u64,
+    schedule: Option<SplitSchedule>,
+    clock: SegmentClock,
+    // Auto gain through the current segment, which starts where the last
+    // one's left off since the gain itself carries on across the split
+    gain_trajectory: Vec<GainPoint>,
+    completed: Vec<FinishedSegment>,
+    error: Option<String>,
+}
+
+impl SegmentWriter {
+    fn new(base_path: PathBuf, spec: hound::WavSpec, align: Option<SegmentAlign>) -> Result<Self, String> {
+        let sample_rate = spec.sample_rate as u64;
+        let (schedule, frames_left) = match align {
+            Some(align) => {
+                let now = SystemTime::now();
+                let interval = Duration::from_secs(align.interval_secs());
+                let next_boundary = next_boundary(now, align.interval_secs());
+                let first = next_boundary.duration_since(now).unwrap_or_default();
+                let schedule = SplitSchedule {
+                    interval,
+                    segment_frames: interval.as_secs() * sample_rate,
+                    next_boundary,
+                };
+                // The first segment only runs up to the next boundary
+                (Some(schedule), (first.as_secs_f64() * sample_rate as f64).round() as u64)
+            }
+            None => (None, u64::MAX),
+        };
+
+        let mut writer = Self {
+            base_path,
+            spec,
+            sink: None,
+            index: 1,
+            frames_left,
+            schedule,
+            clock: SegmentClock::default(),
+            gain_trajectory: Vec::new(),
+            completed: Vec::new(),
+            error: None,
+        };
+        writer.sink = Some(WavSink::create(&writer.current_path(), spec)?);
+        Ok(writer)
+    }
+
+    fn current_path(&self) -> PathBuf {
+        // A plain recording only gets numbered once a reconnect has split it
+        let index = (self.schedule.is_some() || self.index > 1).then_some(self.index);
+        segment_path(&self.base_path, index, "wav")
+    }
+
+    // `gain_db` is the auto gain `data` was recorded at, when it is on
+    fn write(&mut self, mut data: &[f32], gain_db: Option<f32>) {
+        let channels = self.spec.channels as usize;
+        let now = Instant::now();
+        while !data.is_empty() {
+            if self.frames_left == 0 {
+                self.rotate();
+            }
+            if let Some(gain_db) = gain_db {
+                let at_secs = self.clock.frames as f64 / self.spec.sample_rate as f64;
+                let due = self
+                    .gain_trajectory
+                    .last()
+                    .is_none_or(|point| at_secs - point.at_secs >= GAIN_POINT_SECS);
+                if due {
+                    self.gain_trajectory.push(GainPoint { at_secs, gain_db });
+                }
+            }
+            let frames = ((data.len() / channels) as u64).min(self.frames_left);
+            let (head, rest) = data.split_at(frames as usize * channels);
+            if let Some(sink) = self.sink.as_mut() {
+                if let Err(e) = sink.write(head) {
+                    self.error.get_or_insert(e);
+                }
+            }
+            self.clock.record(now, frames);
+            self.frames_left -= frames;
+            data = rest;
+        }
+    }
+
+    fn rotate(&mut self) {
+        let Some(schedule) = self.schedule.as_mut() else {
+            return;
+        };
+        let boundary = schedule.next_boundary;
+        schedule.next_boundary += schedule.interval;
+        self.frames_left = schedule.segment_frames;
+        self.start_next(boundary);
+    }
+
+    // Start a new segment now, outside the schedule, and return its index.
+    // An aligned recording goes back to splitting on the clock afterwards.
+    fn split_now(&mut self) -> usize {
+        let now = SystemTime::now();
+        if let Some(schedule) = self.schedule.as_mut() {
+            schedule.next_boundary = next_boundary(now, schedule.interval.as_secs());
+            let left = schedule.next_boundary.duration_since(now).unwrap_or_default();
+            self.frames_left = (left.as_secs_f64() * self.spec.sample_rate as f64).round() as u64;
+        }
+        self.start_next(now);
+        self.index
+    }
+
+    fn start_next(&mut self, boundary: SystemTime) {
+        let finished = FinishedSegment {
+            wav_path: self.current_path(),
+            boundary,
+            next_index: self.index + 1,
+            measured_rate: std::mem::take(&mut self.clock).measured_rate(),
+            gain_trajectory: std::mem::take(&mut self.gain_trajectory),
+        };
+        if let Some(sink) = self.sink.take() {
+            match sink.finalize() {
+                Ok(()) => self.completed.push(finished),
+                Err(e) => {
+                    self.error.get_or_insert(e);
+                }
+            }
+        }
+        self.index += 1;
+        match WavSink::create(&self.current_path(), self.spec) {
+            Ok(sink) => self.sink = Some(sink),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<Option<FinishedSegment>, String> {
+        match self.sink.take() {
+            Some(sink) => {
+                sink.finalize()?;
+                Ok(Some(FinishedSegment {
+                    wav_path: self.current_path(),
+                    boundary: SystemTime::now(),
+                    next_index: self.index + 1,
+                    measured_rate: self.clock.measured_rate(),
+                    gain_trajectory: std::mem::take(&mut self.gain_trajectory),
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+// Encode a finished WAV segment into the configured output format, write
+// its metadata alongside and make its auto master
+fn finish_segment(
+    app_handle: &AppHandle,
+    segment: FinishedSegment,
+    config: &RecorderConfig,
+    metadata: &RecordingMetadata,
+) -> Result<PathBuf, String> {
+    let wav_path = &segment.wav_path;
+    let mut metadata = metadata.clone();
+    metadata.format = config.output_format;
+    metadata.bitrate = config.bitrate;
+    metadata.measured_sample_rate = segment.measured_rate;
+    metadata.clock_drift_ppm = segment
+        .measured_rate
+        .map(|rate| (rate / metadata.sample_rate as f64 - 1.0) * 1e6);
+    metadata.gain_trajectory = config.auto_gain.map(|_| segment.gain_trajectory);
+    let output = if config.output_format == OutputFormat::Wav {
+        if let Some(labels) = metadata.channel_labels.as_ref() {
+            metadata::embed_channel_labels(wav_path, labels)?;
+        }
+        metadata::write(wav_path, &metadata)?;
+        wav_path.to_path_buf()
+    } else {
+        let output = wav_path.with_extension(config.output_format.extension());
+        metadata.average_kbps = encode::encode_wav(wav_path, &output, config.output_format, config.bitrate)?;
+        metadata::write(&output, &metadata)?;
+        output
+    };
+    // On the disk before stop reports it. Opened for writing since Windows
+    // won't flush a read-only handle, and before permissions can forbid that.
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(&output)
+        .and_then(|file| file.sync_all())
+        .map_err(|e| format!("Failed to flush recording to disk: {}", e))?;
+
+    // Made from the WAV while it is still around; a master that fails only
+    // costs the shareable copy, never the recording
+    let mut finished = vec![output.clone(), metadata::sidecar_path(&output)];
+    if let Some(spec) = config.auto_master.as_ref() {
+        let master_path = master::master_path(&output, spec.format);
+        match master::make_master(wav_path, &master_path, spec) {
+            Ok(()) => {
+                let _ = app_handle.emit(
+                    "master-created",
+                    MasterEvent {
+                        raw_path: output.to_string_lossy().to_string(),
+                        master_path: master_path.to_string_lossy().to_string(),
+                    },
+                );
+                finished.push(master_path);
+            }
+            Err(e) => session::update(&config.session, |session| {
+                session.warnings.push(format!("Failed to make master of {}: {}", output.display(), e));
+            }),
+        }
+    }
+
+    if output != *wav_path {
+        std::fs::remove_file(wav_path).map_err(|e| format!("Failed to remove intermediate WAV file: {}", e))?;
+    }
+    // Only once the files are in their final form, so nothing rewrites them
+    // afterwards with the default mode
+    if let Some(permissions) = config.file_permissions {
+        for path in &finished {
+            if let Err(e) = permissions.apply(path) {
+                session::update(&config.session, |session| session.warnings.push(e));
+            }
+        }
+    }
+    Ok(output)
+}
+
+// Where the recording's audio comes from: its own stream, or the kept one
+enum Input {
+    Owned(cpal::Stream),
+    Kept(Arc<Mutex<Option<KeptInput>>>),
+}
+
+// The recording's audio callback, shared by every stream it opens so capture
+// can move to a reconnected device without losing its state
+type SharedConsumer = Arc<Mutex<InputConsumer>>;
+
+#[derive(Clone)]
+struct StreamSetup {
+    config: cpal::StreamConfig,
+    sample_format: cpal::SampleFormat,
+}
+
+// Signs that the input device has gone away
+#[derive(Clone)]
+struct InputHealth {
+    // Set when the host reports the device as unavailable
+    lost: Arc<Mutex<bool>>,
+    // Some hosts just stop calling back, so a long stall counts as lost too
+    last_data: Arc<Mutex<Instant>>,
+}
+
+impl InputHealth {
+    fn new() -> Self {
+        Self {
+            lost: Arc::new(Mutex::new(false)),
+            last_data: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    fn reset(&self) {
+        if let Ok(mut lost) = self.lost.lock() {
+            *lost = false;
+        }
+        if let Ok(mut last_data) = self.last_data.lock() {
+            *last_data = Instant::now();
+        }
+    }
+
+    fn is_lost(&self) -> bool {
+        self.lost.lock().map(|lost| *lost).unwrap_or(false)
+            || self
+                .last_data
+                .lock()
+                .map(|last_data| last_data.elapsed() >= Duration::from_millis(STALL_MS))
+                .unwrap_or(false)
+    }
+}
+
+// Start capturing from `device` into `consumer`, on the kept stream in
+// keep-alive mode (reusing the one in `kept` when `reuse` is set)
+fn open_input(
+    device: cpal::Device,
+    setup: &StreamSetup,
+    config: &RecorderConfig,
+    kept: &mut Option<KeptInput>,
+    reuse: bool,
+    consumer: &SharedConsumer,
+    health: &InputHealth,
+) -> Result<Input, String> {
+    let consumer = consumer.clone();
+    let last_data = health.last_data.clone();
+    let forward = move |data: &[f32]| {
+        if let Ok(mut last_data) = last_data.lock() {
+            *last_data = Instant::now();
+        }
+        if let Ok(mut consumer) = consumer.lock() {
+            consumer(data);
+        }
+    };
+    match config.keep_alive_idle {
+        Some(idle_timeout) => {
+            if !reuse {
+                *kept = Some(KeptInput::open(device, setup.config.clone(), setup.sample_format, idle_timeout)?);
+            }
+            if let Some(input) = kept.as_ref() {
+                input.attach(Box::new(forward))?;
+            }
+            Ok(Input::Kept(config.kept_input.clone()))
+        }
+        None => {
+            let lost = health.lost.clone();
+            let stream = capture::build_timed_input_stream(
+                &device,
+                &setup.config,
+                setup.sample_format,
+                move |data, _| forward(data),
+                move |err| match err {
+                    cpal::StreamError::DeviceNotAvailable => {
+                        if let Ok(mut lost) = lost.lock() {
+                            *lost = true;
+                        }
+                    }
+                    err => eprintln!("Stream error: {}", err),
+                },
+            )?;
+            stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+            Ok(Input::Owned(stream))
+        }
+    }
+}
+
+// Stop the callbacks; a kept stream is released when its device is gone and
+// otherwise left open for the next recording
+fn close_input(input: Input, release: bool) -> Result<(), String> {
+    match input {
+        Input::Owned(stream) => drop(stream),
+        Input::Kept(kept) => {
+            let mut kept = kept.lock().map_err(|e| e.to_string())?;
+            if release {
+                if let Some(input) = kept.take() {
+                    input.release();
+                }
+            } else if let Some(input) = kept.as_ref() {
+                input.detach()?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Poll for a device named `name` until it reappears and its stream reopens,
+// recording is stopped, or `timeout` runs out
+fn reconnect(
+    name: &str,
+    setup: &StreamSetup,
+    config: &RecorderConfig,
+    consumer: &SharedConsumer,
+    health: &InputHealth,
+    is_recording: &Mutex<bool>,
+    timeout: Duration,
+) -> Result<Option<Input>, String> {
+    let started = Instant::now();
+    while started.elapsed() < timeout {
+        thread::sleep(Duration::from_millis(RECONNECT_POLL_MS));
+        if !*is_recording.lock().map_err(|e| e.to_string())? {
+            return Ok(None);
+        }
+        let Ok(device) = capture::find_input_device(Some(name)) else {
+            continue;
+        };
+        health.reset();
+        let mut kept = config.kept_input.lock().map_err(|e| e.to_string())?;
+        // A device that has only just reappeared may not open yet
+        if let Ok(input) = open_input(device, setup, config, &mut kept, false, consumer, health) {
+            return Ok(Some(input));
+        }
+    }
+    Ok(None)
+}
+
+// The device to record from and the config it runs at: the first of
+// device_priority that is present and accepts the requested config, or the
+// role's default device when there is no list. Also returns the devices
+// passed over on the way.
+pub fn select_device(
+    role: DeviceRole,
+    priority: &[String],
+    sample_rate: Option<SampleRateRequest>,
+    buffer_frames: Option<u32>,
+) -> Result<(cpal::Device, cpal::SupportedStreamConfig, Vec<DeviceAttempt>), String> {
+    if priority.is_empty() {
+        let device = capture::find_default_input_device(role)?;
+        let supported = input_config(&device, sample_rate, buffer_frames)?;
+        return Ok((device, supported, Vec::new()));
+    }
+    let mut skipped = Vec::new();
+    for name in priority {
+        let attempt = capture::find_input_device(Some(name))
+            .and_then(|device| input_config(&device, sample_rate, buffer_frames).map(|supported| (device, supported)));
+        match attempt {
+            Ok((device, supported)) => return Ok((device, supported, skipped)),
+            Err(error) => skipped.push(DeviceAttempt {
+                device: name.clone(),
+                error,
+            }),
+        }
+    }
+    let tried: Vec<String> = skipped
+        .iter()
+        .map(|attempt| format!("{} ({})", attempt.device, attempt.error))
+        .collect();
+    Err(format!("No device in the priority list could record: {}", tried.join("; ")))
+}
+
+// Config for `device` that meets the requested rate and buffer size
+fn input_config(
+    device: &cpal::Device,
+    request: Option<SampleRateRequest>,
+    buffer_frames: Option<u32>,
+) -> Result<cpal::SupportedStreamConfig, String> {
+    let supported = capture::select_input_config(
+        device,
+        request.map(|request| request.rate),
+        request.is_some_and(|request| request.nearest_rate),
+    )?;
+    check_buffer_frames(&supported, buffer_frames)?;
+    Ok(supported)
+}
+
+pub fn check_buffer_frames(supported: &cpal::SupportedStreamConfig, buffer_frames: Option<u32>) -> Result<(), String> {
+    if let (Some(frames), cpal::SupportedBufferSize::Range { min, max }) = (buffer_frames, *supported.buffer_size()) {
+        if !(min..=max).contains(&frames) {
+            return Err(format!("Input device buffers must be between {} and {} frames", min, max));
+        }
+    }
+    Ok(())
+}
+
+fn stream_setup(supported: cpal::SupportedStreamConfig, config: &RecorderConfig) -> StreamSetup {
+    let sample_format = supported.sample_format();
+    let mut stream_config: cpal::StreamConfig = supported.into();
+    if let Some(frames) = config.buffer_frames {
+        stream_config.buffer_size = cpal::BufferSize::Fixed(frames);
+    }
+    StreamSetup {
+        config: stream_config,
+        sample_format,
+    }
+}
+
+// The stream feeding the recording and how it was opened
+struct ActiveInput {
+    input: Option<Input>,
+    setup: StreamSetup,
+    // The recording's callback, behind a converter after switching to a
+    // device that doesn't match the session
+    consumer: SharedConsumer,
+}
+
+// Setup for recording from `device` in a session opened with `session`:
+// the session's own rate and channels when the device offers them, otherwise
+// whatever it would have been picked with at the start
+fn switch_setup(device: &cpal::Device, session: &StreamSetup, config: &RecorderConfig) -> Result<StreamSetup, String> {
+    let rate = session.config.sample_rate;
+    let matching = device
+        .supported_input_configs()
+        .map_err(|e| format!("Failed to get input configs: {}", e))?
+        .filter(|range| range.channels() == session.config.channels)
+        .find(|range| (range.min_sample_rate()..=range.max_sample_rate()).contains(&rate))
+        .map(|range| range.with_sample_rate(rate));
+    let supported = match matching {
+        Some(supported) => {
+            check_buffer_frames(&supported, config.buffer_frames)?;
+            supported
+        }
+        None => input_config(device, config.sample_rate, config.buffer_frames)?,
+    };
+    Ok(stream_setup(supported, config))
+}
+
+// `consumer` as fed by a stream opened with `from`, converting its rate and
+// channels to the session's `to` so the recording sees no difference
+fn adapt_consumer(
+    consumer: &SharedConsumer,
+    from: &cpal::StreamConfig,
+    to: &cpal::StreamConfig,
+) -> Result<SharedConsumer, String> {
+    if from.channels == to.channels && from.sample_rate == to.sample_rate {
+        return Ok(consumer.clone());
+    }
+    let mixer = (from.channels != to.channels)
+        .then(|| ChannelMixer::new(from.channels, to.channels))
+        .transpose()?;
+    let mut resampler = (from.sample_rate != to.sample_rate)
+        .then(|| Resampler::new(from.sample_rate.0, to.sample_rate.0, to.channels as usize));
+    let inner = consumer.clone();
+    let mut mixed = Vec::new();
+    let mut resampled = Vec::new();
+    let adapter = move |data: &[f32]| {
+        let data = match mixer.as_ref() {
+            Some(mixer) => {
+                mixer.process_interleaved(data, &mut mixed);
+                &mixed[..]
+            }
+            None => data,
+        };
+        let data = match resampler.as_mut() {
+            Some(resampler) => {
+                resampler.process_interleaved(data, &mut resampled);
+                &resampled[..]
+            }
+            None => data,
+        };
+        if let Ok(mut inner) = inner.lock() {
+            inner(data);
+        }
+    };
+    Ok(Arc::new(Mutex::new(Box::new(adapter))))
+}
+
+// Move capture to the device named `name`, starting a new segment. The
+// current device is only let go once the new one is known to fit, and is
+// reopened if the new one then fails to start.
+#[allow(clippy::too_many_arguments)]
+fn switch_input(
+    app_handle: &AppHandle,
+    name: &str,
+    session: &StreamSetup,
+    consumer: &SharedConsumer,
+    active: &mut ActiveInput,
+    writer: &Mutex<SegmentWriter>,
+    output_path: &Mutex<Option<String>>,
+    config: &RecorderConfig,
+    recording_metadata: &mut RecordingMetadata,
+    health: &InputHealth,
+) -> Result<(), String> {
+    let device = capture::find_input_device(Some(name))?;
+    let setup = switch_setup(&device, session, config)?;
+    let switched_consumer = adapt_consumer(consumer, &setup.config, &session.config)?;
+
+    // Everything the old device captured stays in the segment it started
+    if let Some(old) = active.input.take() {
+        close_input(old, true)?;
+    }
+    let (index, completed) = {
+        let mut writer = writer.lock().map_err(|e| e.to_string())?;
+        (writer.split_now(), std::mem::take(&mut writer.completed))
+    };
+    finish_completed(app_handle, completed, output_path, config, recording_metadata)?;
+
+    health.reset();
+    let mut kept = config.kept_input.lock().map_err(|e| e.to_string())?;
+    match open_input(device, &setup, config, &mut kept, false, &switched_consumer, health) {
+        Ok(input) => {
+            active.input = Some(input);
+            active.setup = setup;
+            active.consumer = switched_consumer;
+        }
+        Err(e) => {
+            let reopened = capture::find_input_device(Some(&recording_metadata.device))
+                .and_then(|old| open_input(old, &active.setup, config, &mut kept, false, &active.consumer, health));
+            active.input = reopened.ok();
+            return Err(e);
+        }
+    }
+    drop(kept);
+
+    let from = std::mem::replace(&mut recording_metadata.device, name.to_string());
+    session::update(&config.session, |session| session.device = Some(name.to_string()));
+    let _ = app_handle.emit(
+        "device-switched",
+        DeviceSwitchedEvent {
+            from,
+            to: name.to_string(),
+            index,
+            path: segment_path(&config.base_path, Some(index), config.output_format.extension())
+                .to_string_lossy()
+                .to_string(),
+            converted: active.setup.config.sample_rate != session.config.sample_rate
+                || active.setup.config.channels != session.config.channels,
+        },
+    );
+    Ok(())
+}
+
+pub fn record_audio(
+    app_handle: AppHandle,
+    is_recording: Arc<Mutex<bool>>,
+    output_path: Arc<Mutex<Option<String>>>,
+    mut config: RecorderConfig,
+) -> Result<(), String> {
+    let (device, supported, skipped) = select_device(
+        config.device_role,
+        &config.device_priority,
+        config.sample_rate,
+        config.buffer_frames,
+    )?;
+    if !config.device_priority.is_empty() {
+        let selected = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+        for attempt in &skipped {
+            session::update(&config.session, |session| {
+                session.warnings.push(format!("Skipped {}: {}", attempt.device, attempt.error));
+            });
+        }
+        let _ = app_handle.emit(
+            "device-selected",
+            DeviceSelectedEvent {
+                device: selected,
+                priority: skipped.len(),
+                skipped,
+            },
+        );
+    }
+    let request = config.sample_rate;
+    let session_setup = stream_setup(supported, &config);
+    let sample_format = session_setup.sample_format;
+    let device_rate = session_setup.config.sample_rate.0;
+    let input_channels = session_setup.config.channels;
+    let channels = config.output_channels.unwrap_or(input_channels);
+    let mixer = (channels != input_channels)
+        .then(|| ChannelMixer::new(input_channels, channels))
+        .transpose()?;
+    if config.output_format == OutputFormat::Mp3 && channels > 2 {
+        return Err("MP3 output supports at most 2 channels".to_string());
+    }
+    if let Some(labels) = config.channel_labels.as_ref() {
+        metadata::validate_channel_labels(labels, channels)?;
+    }
+
+    let mut resampler = None;
+    let mut output_rate = device_rate;
+    if let Some(request) = request.filter(|request| request.rate != device_rate) {
+        if request.resample {
+            resampler = Some(Resampler::new(device_rate, request.rate, channels as usize));
+            output_rate = request.rate;
+        }
+        session::update(&config.session, |session| {
+            session.warnings.push(format!(
+                "Device runs at {} Hz instead of the requested {} Hz; recording at {} Hz",
+                device_rate, request.rate, output_rate
+            ));
+        });
+        let _ = app_handle.emit(
+            "config-adjusted",
+            ConfigAdjustedEvent {
+                requested_rate: request.rate,
+                device_rate,
+                output_rate,
+                resampled: request.resample,
+            },
+        );
+    }
+    let spec = wav::pcm16_spec(channels, output_rate);
+    let mut recording_metadata = RecordingMetadata {
+        device: device.name().unwrap_or_else(|_| "Unknown device".to_string()),
+        sample_rate: output_rate,
+        channels,
+        format: config.output_format,
+        bitrate: config.bitrate,
+        average_kbps: None,
+        started_at_unix_ms: unix_ms(SystemTime::now()),
+        discard_initial_ms: config.discard_initial_ms,
+        channel_labels: config.channel_labels.clone(),
+        measured_sample_rate: None,
+        clock_drift_ppm: None,
+        emphasis: config.pre_emphasis,
+        gain_trajectory: None,
+    };
+    session::update(&config.session, |session| session.device = Some(recording_metadata.device.clone()));
+
+    let writer = SegmentWriter::new(config.base_path.clone(), spec, config.segment_align)?;
+    let writer = Arc::new(Mutex::new(writer));
+
+    let mut tap = None;
+    let mut frame_sender = None;
+    if let Some(frame_stream) = config.frame_stream.take() {
+        let frame_tap = Arc::new(Mutex::new(FrameTap::new(
+            spec.channels,
+            spec.sample_rate,
+            frame_stream.options.pcm,
+        )));
+        frame_sender = Some(frames::spawn_sender(
+            frame_stream,
+            frame_tap.clone(),
+            is_recording.clone(),
+            config.window_visible.clone(),
+        ));
+        tap = Some(frame_tap);
+    }
+
+    // A kept stream is reused when it is still open on the same device and
+    // config; any other one is closed first so the device isn't busy
+    let key = StreamKey {
+        device: recording_metadata.device.clone(),
+        sample_rate: device_rate,
+        channels: input_channels,
+        sample_format,
+        buffer_frames: config.buffer_frames,
+    };
+    let mut kept = config.kept_input.lock().map_err(|e| e.to_string())?;
+    let reusable = config.keep_alive_idle.is_some()
+        && kept.as_ref().is_some_and(|input| input.is_open() && *input.key() == key);
+    if !reusable {
+        if let Some(input) = kept.take() {
+            input.release();
+        }
+    }
+
+    let writer_ref = writer.clone();
+    // Separate from is_recording so capture can run on through the post-roll
+    let capturing = Arc::new(Mutex::new(true));
+    let capturing_ref = capturing.clone();
+    let mut mixed = Vec::new();
+    let mut resampled = Vec::new();
+    // Monitoring is a convenience; the recording goes ahead without it
+    let monitor = config.monitor_gain.clone().and_then(|gain| {
+        Monitor::start(app_handle.clone(), channels, output_rate, gain)
+            .inspect_err(|e| {
+                session::update(&config.session, |session| {
+                    session.warnings.push(format!("Input monitoring unavailable: {}", e));
+                });
+            })
+            .ok()
+    });
+    let monitor_feed = monitor.as_ref().map(Monitor::feed);
+    let mut filters = LiveFilters::new(
+        &config.filters,
+        config.filter_toggles.clone(),
+        output_rate,
+        channels as usize,
+    );
+    let mut auto_gain = config.auto_gain.map(|settings| {
+        AutoGain::new(output_rate, channels as usize, settings.target_dbfs, settings.max_gain_db)
+    });
+    let mut emphasis = config
+        .pre_emphasis
+        .map(|coefficient| Emphasis::pre(coefficient, channels as usize));
+    let mut processed = Vec::new();
+    // A stream that is already running has no start-up noise to skip
+    let discard_ms = if reusable { 0 } else { config.discard_initial_ms };
+    let mut discard_samples = (discard_ms * device_rate as u64 / 1000) as usize * input_channels as usize;
+    let on_data = move |data: &[f32]| {
+        if !capturing_ref.lock().map(|capturing| *capturing).unwrap_or(false) {
+            return;
+        }
+        let skipped = discard_samples.min(data.len());
+        discard_samples -= skipped;
+        let data = &data[skipped..];
+        if data.is_empty() {
+            return;
+        }
+        let data = match mixer.as_ref() {
+            Some(mixer) => {
+                mixer.process_interleaved(data, &mut mixed);
+                &mixed[..]
+            }
+            None => data,
+        };
+        let data = match resampler.as_mut() {
+            Some(resampler) => {
+                resampler.process_interleaved(data, &mut resampled);
+                &resampled[..]
+            }
+            None => data,
+        };
+        processed.clear();
+        processed.extend_from_slice(data);
+        filters.process_interleaved(&mut processed);
+        // Heard through the filters so they can be compared by ear, but
+        // before auto gain and emphasis
+        if let Some(feed) = monitor_feed.as_ref() {
+            feed.push(&processed);
+        }
+        if let Some(auto_gain) = auto_gain.as_mut() {
+            auto_gain.process_interleaved(&mut processed);
+        }
+        if let Some(emphasis) = emphasis.as_mut() {
+            emphasis.process_interleaved(&mut processed);
+        }
+        let data = &processed[..];
+        if let Ok(mut writer) = writer_ref.lock() {
+            writer.write(data, auto_gain.as_ref().map(AutoGain::gain_db));
+        }
+        if let Some(Ok(mut tap)) = tap.as_ref().map(|tap| tap.lock()) {
+            tap.push(data);
+        }
+    };
+    let consumer: SharedConsumer = Arc::new(Mutex::new(Box::new(on_data)));
+    let health = InputHealth::new();
+    let input = open_input(device, &session_setup, &config, &mut kept, reusable, &consumer, &health)?;
+    drop(kept);
+    let mut active = ActiveInput {
+        input: Some(input),
+        setup: session_setup.clone(),
+        consumer: consumer.clone(),
+    };
+
+    // Why the recording stopped by itself, if it did
+    let mut ended_early = None;
+    let watched = loop {
+        let watch = match watch_segments(
+            &app_handle,
+            &is_recording,
+            &output_path,
+            &writer,
+            &mut config,
+            &recording_metadata,
+            &health,
+        ) {
+            Ok(WatchEnd::SwitchDevice(name)) => {
+                let switched = switch_input(
+                    &app_handle,
+                    &name,
+                    &session_setup,
+                    &consumer,
+                    &mut active,
+                    &writer,
+                    &output_path,
+                    &config,
+                    &mut recording_metadata,
+                    &health,
+                );
+                let Err(error) = switched else {
+                    continue;
+                };
+                let kept_on = if active.input.is_some() {
+                    format!("still recording from {}", recording_metadata.device)
+                } else {
+                    format!("{} could not be reopened either", recording_metadata.device)
+                };
+                session::update(&config.session, |session| {
+                    session.warnings.push(format!("Failed to switch to {}: {}; {}", name, error, kept_on));
+                });
+                let _ = app_handle.emit("device-switch-failed", DeviceSwitchFailedEvent { device: name, error });
+                if active.input.is_some() {
+                    continue;
+                }
+                // Without either device, carry on as if the device was lost
+                Ok(WatchEnd::DeviceLost)
+            }
+            watch => watch,
+        };
+        if !matches!(watch, Ok(WatchEnd::DeviceLost)) {
+            break watch.map(|_| ());
+        }
+
+        // The device is gone: close what is left of its stream and, if
+        // allowed, wait for it to come back under the same name
+        let device_name = recording_metadata.device.clone();
+        let lost_at = Instant::now();
+        let _ = app_handle.emit(
+            "recording-device-lost",
+            DeviceLostEvent {
+                device: device_name.clone(),
+            },
+        );
+        if let Some(lost) = active.input.take() {
+            if let Err(e) = close_input(lost, true) {
+                break Err(e);
+            }
+        }
+        let reconnected = match config.reconnect_timeout {
+            Some(timeout) => reconnect(
+                &device_name,
+                &active.setup,
+                &config,
+                &active.consumer,
+                &health,
+                &is_recording,
+                timeout,
+            ),
+            None => Ok(None),
+        };
+        match reconnected {
+            Ok(Some(reopened)) => {
+                active.input = Some(reopened);
+                let index = match writer.lock() {
+                    Ok(mut writer) => writer.split_now(),
+                    Err(e) => break Err(e.to_string()),
+                };
+                let offline_ms = lost_at.elapsed().as_millis() as u64;
+                session::update(&config.session, |session| {
+                    session.warnings.push(format!(
+                        "{} was disconnected for {:.1} s; recording resumed in segment {}",
+                        device_name,
+                        offline_ms as f64 / 1000.0,
+                        index
+                    ));
+                });
+                let _ = app_handle.emit(
+                    "recording-reconnected",
+                    ReconnectedEvent {
+                        device: device_name,
+                        offline_ms,
+                        index,
+                        path: segment_path(&config.base_path, Some(index), config.output_format.extension())
+                            .to_string_lossy()
+                            .to_string(),
+                    },
+                );
+            }
+            Ok(None) => {
+                let reason = format!("{} was disconnected and did not come back; recording ended early", device_name);
+                session::update(&config.session, |session| {
+                    session.stopped_unix_ms.get_or_insert(unix_ms(SystemTime::now()));
+                    session.warnings.push(reason.clone());
+                });
+                if let Ok(mut recording) = is_recording.lock() {
+                    *recording = false;
+                }
+                ended_early = Some(reason);
+                break Ok(());
+            }
+            Err(e) => break Err(e),
+        }
+    };
+
+    // Stop the callbacks before finalizing the last segment
+    *capturing.lock().map_err(|e| e.to_string())? = false;
+    if let Some(input) = active.input {
+        close_input(input, false)?;
+    }
+    drop(monitor);
+    if let Some(sender) = frame_sender {
+        let _ = sender.join();
+    }
+    watched?;
+    let last = writer.lock().map_err(|e| e.to_string())?.finish()?;
+    if let Some(segment) = last {
+        let path = finish_segment(&app_handle, segment, &config, &recording_metadata)?;
+        *output_path.lock().map_err(|e| e.to_string())? = Some(path.to_string_lossy().to_string());
+    }
+    // Nobody will call stop_recording for this one, so say where it ended up
+    if let Some(reason) = ended_early {
+        let path = output_path.lock().map_err(|e| e.to_string())?.clone();
+        let _ = app_handle.emit("recording-ended", RecordingEndedEvent { reason, path });
+    }
+
+    Ok(())
+}
+
+enum WatchEnd {
+    Stopped,
+    DeviceLost,
+    // The frontend asked to move to this device
+    SwitchDevice(String),
+}
+
+// Poll while recording, announcing finished segments, until recording stops
+// and the post-roll has been captured, or the device goes away
+fn watch_segments(
+    app_handle: &AppHandle,
+    is_recording: &Mutex<bool>,
+    output_path: &Mutex<Option<String>>,
+    writer: &Mutex<SegmentWriter>,
+    config: &mut RecorderConfig,
+    recording_metadata: &RecordingMetadata,
+    health: &InputHealth,
+) -> Result<WatchEnd, String> {
+    let poll = Duration::from_millis(100);
+    let mut stop_requested: Option<Instant> = None;
+    let mut space_checked = Instant::now();
+    loop {
+        let sleep = match stop_requested {
+            Some(requested) => poll.min(config.stop_post_roll.saturating_sub(requested.elapsed())),
+            None => poll,
+        };
+        thread::sleep(sleep);
+
+        if config.low_space.is_some() && space_checked.elapsed() >= Duration::from_secs(LOW_SPACE_CHECK_SECS) {
+            space_checked = Instant::now();
+            downgrade_if_low(app_handle, output_path, writer, config, recording_metadata)?;
+        }
+
+        let (completed, error) = {
+            let mut writer = writer.lock().map_err(|e| e.to_string())?;
+            (std::mem::take(&mut writer.completed), writer.error.take())
+        };
+        if let Some(e) = error {
+            *is_recording.lock().map_err(|e| e.to_string())? = false;
+            return Err(e);
+        }
+        finish_completed(app_handle, completed, output_path, config, recording_metadata)?;
+
+        if !*is_recording.lock().map_err(|e| e.to_string())? {
+            let requested = *stop_requested.get_or_insert_with(Instant::now);
+            if requested.elapsed() >= config.stop_post_roll {
+                return Ok(WatchEnd::Stopped);
+            }
+        } else if config.reconnect_timeout.is_some() && health.is_lost() {
+            return Ok(WatchEnd::DeviceLost);
+        } else if let Some(name) = config.device_switch.lock().map_err(|e| e.to_string())?.take() {
+            return Ok(WatchEnd::SwitchDevice(name));
+        }
+    }
+}
+
+// Once free space drops below the policy's floor, split and move to the next
+// smaller preset from the new segment on, rather than at the next scheduled
+// split. The segment just closed is encoded in the format it was recorded for.
+fn downgrade_if_low(
+    app_handle: &AppHandle,
+    output_path: &Mutex<Option<String>>,
+    writer: &Mutex<SegmentWriter>,
+    config: &mut RecorderConfig,
+    recording_metadata: &RecordingMetadata,
+) -> Result<(), String> {
+    let Some(policy) = config.low_space else {
+        return Ok(());
+    };
+    let directory = config.base_path.parent().unwrap_or(Path::new("."));
+    // A failed check is retried next time rather than ending the recording
+    let Ok(free_bytes) = disk::available_space(directory) else {
+        return Ok(());
+    };
+    if free_bytes >= policy.min_free_mb.saturating_mul(1024 * 1024) {
+        return Ok(());
+    }
+    let (rate, channels) = (recording_metadata.sample_rate, recording_metadata.channels);
+    let current = if config.output_format.is_pcm() {
+        format::pcm_bytes_per_second(rate, channels)
+    } else {
+        config.bitrate.unwrap_or(encode::DEFAULT_BITRATE).approx_kbps() as u64 * 1000 / 8
+    };
+    let Some(preset) = format::smaller_preset(current, rate, channels) else {
+        // Nothing smaller to go to; say so once
+        config.low_space = None;
+        session::update(&config.session, |session| {
+            session.warnings.push(format!(
+                "Free space is down to {} MB and there is no smaller format to switch to",
+                free_bytes / (1024 * 1024)
+            ));
+        });
+        return Ok(());
+    };
+
+    let (index, completed) = {
+        let mut writer = writer.lock().map_err(|e| e.to_string())?;
+        let index = writer.split_now();
+        (index, std::mem::take(&mut writer.completed))
+    };
+    let mut finished = Vec::new();
+    for segment in completed {
+        let (boundary, next_index) = (segment.boundary, segment.next_index);
+        finished.push((boundary, next_index, finish_segment(app_handle, segment, config, recording_metadata)?));
+    }
+    let from = config.output_format;
+    config.output_format = preset.format();
+    config.bitrate = preset.bitrate_kbps().map(BitrateMode::Cbr);
+    for (boundary, next_index, previous) in finished {
+        announce_split(app_handle, output_path, config, boundary, next_index, &previous)?;
+    }
+    session::update(&config.session, |session| {
+        session.warnings.push(format!(
+            "Free space is down to {} MB; recording {:?} quality from segment {}",
+            free_bytes / (1024 * 1024),
+            preset,
+            index
+        ));
+    });
+    let _ = app_handle.emit(
+        "format-downgraded-lowspace",
+        FormatDowngradedEvent {
+            from,
+            preset,
+            format: config.output_format,
+            free_bytes,
+            index,
+            path: segment_path(&config.base_path, Some(index), config.output_format.extension())
+                .to_string_lossy()
+                .to_string(),
+        },
+    );
+    Ok(())
+}
+
+// Finalize segments the writer has closed and announce each split
+fn finish_completed(
+    app_handle: &AppHandle,
+    completed: Vec<FinishedSegment>,
+    output_path: &Mutex<Option<String>>,
+    config: &RecorderConfig,
+    recording_metadata: &RecordingMetadata,
+) -> Result<(), String> {
+    for segment in completed {
+        let (boundary, index) = (segment.boundary, segment.next_index);
+        let previous = finish_segment(app_handle, segment, config, recording_metadata)?;
+        announce_split(app_handle, output_path, config, boundary, index, &previous)?;
+    }
+    Ok(())
+}
+
+// Point the output path at segment `index` and tell the frontend it started
+fn announce_split(
+    app_handle: &AppHandle,
+    output_path: &Mutex<Option<String>>,
+    config: &RecorderConfig,
+    boundary: SystemTime,
+    index: usize,
+    previous: &Path,
+) -> Result<(), String> {
+    let path = segment_path(&config.base_path, Some(index), config.output_format.extension());
+    let path_str = path.to_string_lossy().to_string();
+    *output_path.lock().map_err(|e| e.to_string())? = Some(path_str.clone());
+    let _ = app_handle.emit(
+        "recording-split",
+        SplitEvent {
+            index,
+            boundary_unix_ms: unix_ms(boundary),
+            previous_path: previous.to_string_lossy().to_string(),
+            path: path_str,
+        },
+    );
+    Ok(())
+}
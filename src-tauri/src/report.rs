@@ -0,0 +1,233 @@
+use std::path::Path;
+use chrono::{Local, TimeZone};
+use tauri::State;
+
+use crate::decode;
+use crate::dynamics::{self, DynamicsVerdict};
+use crate::format::{BitrateMode, OutputFormat};
+use crate::metadata::{self, RecordingMetadata};
+use crate::timeline::{self, TimelineEntry, TimelineFormat};
+use crate::RecordingState;
+
+// Samples at or above this are at full scale
+const CLIP_LEVEL: f32 = 0.999;
+// Full-scale samples in a row before it counts as clipping rather than a
+// peak that happens to touch 0 dBFS
+const CLIP_RUN: usize = 3;
+
+#[derive(Default)]
+struct ChannelClipping {
+    samples: u64,
+    passages: u64,
+    run: usize,
+}
+
+impl ChannelClipping {
+    fn push(&mut self, sample: f32) {
+        if sample.abs() < CLIP_LEVEL {
+            self.run = 0;
+            return;
+        }
+        self.samples += 1;
+        self.run += 1;
+        if self.run == CLIP_RUN {
+            self.passages += 1;
+        }
+    }
+}
+
+fn clipping(path: &Path) -> Result<Vec<ChannelClipping>, String> {
+    let mut source = decode::open(path)?;
+    let channels = source.info().channels as usize;
+    let mut clipping: Vec<ChannelClipping> = (0..channels).map(|_| ChannelClipping::default()).collect();
+    let mut buffer = Vec::new();
+    while source.read_chunk(&mut buffer)? > 0 {
+        for frame in buffer.chunks_exact(channels) {
+            for (sample, channel) in frame.iter().zip(clipping.iter_mut()) {
+                channel.push(*sample);
+            }
+        }
+    }
+    Ok(clipping)
+}
+
+// A readable summary of a recording to keep with it or paste into a bug
+// report: the file, how it was recorded, its levels and clipping, the given
+// markers and, for the latest recording, anything it had to work around. As
+// markdown, so it reads fine as plain text too.
+#[tauri::command]
+pub async fn recording_report(
+    state: State<'_, RecordingState>,
+    path: String,
+    markers: Option<Vec<TimelineEntry>>,
+) -> Result<String, String> {
+    let file = Path::new(&path);
+    let size = std::fs::metadata(file)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .len();
+    let info = decode::open(file)?.info();
+    let dynamics = dynamics::analyze(file)?;
+    let clipping = clipping(file)?;
+    // Files from elsewhere have no sidecar, which leaves less to report
+    let metadata = metadata::read(file).ok();
+    // The session only knows about the recording it made last
+    let latest = state.output_path.lock().map_err(|e| e.to_string())?.as_deref() == Some(path.as_str());
+    let warnings = if latest {
+        state
+            .session
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_ref()
+            .map(|session| session.warnings.clone())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let labels = metadata.as_ref().and_then(|metadata| metadata.channel_labels.clone()).unwrap_or_default();
+    let channel_name = |index: usize| labels.get(index).cloned().unwrap_or_else(|| format!("Channel {}", index + 1));
+    let mut lines = vec![format!("# {}", name), String::new()];
+
+    lines.push("## File".to_string());
+    lines.push(String::new());
+    lines.push(format!("- Path: {}", path));
+    lines.push(format!("- Size: {:.1} MB", size as f64 / 1_000_000.0));
+    let extension = file.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    let format = OutputFormat::from_extension(extension).or(metadata.as_ref().map(|metadata| metadata.format));
+    if let Some(format) = format {
+        lines.push(format!("- Format: {}", format_name(format)));
+    }
+    lines.push(format!("- Duration: {}", timeline::timestamp(dynamics.duration_secs, TimelineFormat::Vtt)));
+    lines.push(format!("- Sample rate: {} Hz", info.sample_rate));
+    lines.push(format!("- Channels: {}", info.channels));
+    if let Some(bits) = info.bits_per_sample {
+        lines.push(format!("- Bit depth: {}-bit", bits));
+    }
+    lines.push(String::new());
+
+    if let Some(metadata) = metadata.as_ref() {
+        lines.push("## Recording".to_string());
+        lines.push(String::new());
+        lines.extend(recording_lines(metadata));
+        lines.push(String::new());
+    }
+
+    lines.push("## Levels".to_string());
+    lines.push(String::new());
+    lines.push("| Channel | Peak | RMS | Crest factor | DR | Loud/quiet spread |".to_string());
+    lines.push("| --- | --- | --- | --- | --- | --- |".to_string());
+    for (index, channel) in dynamics.channels.iter().enumerate() {
+        lines.push(format!(
+            "| {} | {:.1} dBFS | {:.1} dBFS | {:.1} dB | {} | {:.1} dB |",
+            channel_name(index),
+            channel.peak_dbfs,
+            channel.rms_dbfs,
+            channel.crest_factor_db,
+            channel.dr.map_or("-".to_string(), |dr| format!("{:.1}", dr)),
+            channel.range_db
+        ));
+    }
+    lines.push(String::new());
+    match (dynamics.dr, dynamics.verdict) {
+        (Some(dr), Some(verdict)) => lines.push(format!("- DR: {} ({})", dr, verdict_name(verdict))),
+        _ => lines.push("- DR: silent".to_string()),
+    }
+    lines.push(format!("- Crest factor: {:.1} dB", dynamics.crest_factor_db));
+    lines.push(format!("- Loud/quiet spread: {:.1} dB", dynamics.range_db));
+    lines.push(String::new());
+
+    lines.push("## Clipping".to_string());
+    lines.push(String::new());
+    if clipping.iter().all(|channel| channel.passages == 0) {
+        lines.push("None detected.".to_string());
+    }
+    for (index, channel) in clipping.iter().enumerate().filter(|(_, channel)| channel.passages > 0) {
+        lines.push(format!(
+            "- {}: {} clipped passages, {} samples at full scale",
+            channel_name(index),
+            channel.passages,
+            channel.samples
+        ));
+    }
+    lines.push(String::new());
+
+    let mut markers = markers.unwrap_or_default();
+    if !markers.is_empty() {
+        markers.sort_by(|a, b| a.start_secs.total_cmp(&b.start_secs));
+        lines.push("## Markers".to_string());
+        lines.push(String::new());
+        for marker in &markers {
+            let start = timeline::timestamp(marker.start_secs, TimelineFormat::Vtt);
+            match marker.end_secs {
+                Some(end) => lines.push(format!(
+                    "- {} to {}: {}",
+                    start,
+                    timeline::timestamp(end, TimelineFormat::Vtt),
+                    marker.label
+                )),
+                None => lines.push(format!("- {}: {}", start, marker.label)),
+            }
+        }
+        lines.push(String::new());
+    }
+
+    if !warnings.is_empty() {
+        lines.push("## Warnings".to_string());
+        lines.push(String::new());
+        lines.extend(warnings.iter().map(|warning| format!("- {}", warning)));
+        lines.push(String::new());
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn recording_lines(metadata: &RecordingMetadata) -> Vec<String> {
+    let mut lines = vec![format!("- Device: {}", metadata.device)];
+    if let Some(started) = Local.timestamp_millis_opt(metadata.started_at_unix_ms as i64).single() {
+        lines.push(format!("- Started: {}", started.format("%Y-%m-%d %H:%M:%S")));
+    }
+    let average = metadata.average_kbps.map(|kbps| format!(", averaging {} kbps", kbps)).unwrap_or_default();
+    match metadata.bitrate {
+        Some(BitrateMode::Cbr(kbps)) => lines.push(format!("- Bitrate: {} kbps constant{}", kbps, average)),
+        Some(BitrateMode::Vbr { quality }) => {
+            lines.push(format!("- Bitrate: variable, quality {}{}", quality, average))
+        }
+        Some(BitrateMode::Abr(kbps)) => lines.push(format!("- Bitrate: {} kbps average target{}", kbps, average)),
+        None => {}
+    }
+    if metadata.discard_initial_ms > 0 {
+        lines.push(format!("- Discarded at start: {} ms", metadata.discard_initial_ms));
+    }
+    if let (Some(rate), Some(ppm)) = (metadata.measured_sample_rate, metadata.clock_drift_ppm) {
+        lines.push(format!("- Measured sample rate: {:.1} Hz ({:+.1} ppm clock drift)", rate, ppm));
+    }
+    if let Some(coefficient) = metadata.emphasis {
+        lines.push(format!("- Pre-emphasis: coefficient {}", coefficient));
+    }
+    if let Some(trajectory) = metadata.gain_trajectory.as_ref().filter(|trajectory| !trajectory.is_empty()) {
+        let min = trajectory.iter().map(|point| point.gain_db).fold(f32::INFINITY, f32::min);
+        let max = trajectory.iter().map(|point| point.gain_db).fold(f32::NEG_INFINITY, f32::max);
+        lines.push(format!("- Auto gain: {:+.1} to {:+.1} dB", min, max));
+    }
+    lines
+}
+
+fn format_name(format: OutputFormat) -> &'static str {
+    match format {
+        OutputFormat::Wav => "WAV",
+        OutputFormat::Aiff => "AIFF",
+        OutputFormat::Mp3 => "MP3",
+    }
+}
+
+fn verdict_name(verdict: DynamicsVerdict) -> &'static str {
+    match verdict {
+        DynamicsVerdict::Compressed => "compressed",
+        DynamicsVerdict::Moderate => "moderate",
+        DynamicsVerdict::Dynamic => "dynamic",
+    }
+}
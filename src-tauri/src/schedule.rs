@@ -0,0 +1,285 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use chrono::{Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager, State};
+
+use crate::{RecordingOptions, RecordingState, StopOptions};
+
+const TICK_MS: u64 = 1_000;
+
+// Times are UTC unix seconds throughout, so jobs fire at the same instant
+// whatever the local timezone or DST does in between; local times are only
+// for display
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledRecording {
+    pub id: u64,
+    pub start_unix: i64,
+    pub stop_unix: i64,
+    pub options: RecordingOptions,
+    // Set while the scheduler has the recording running
+    pub active: bool,
+    // Session it started, so a recording the user started since isn't the
+    // one that gets stopped
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleEntry {
+    #[serde(flatten)]
+    pub job: ScheduledRecording,
+    // RFC 3339 in the machine's current timezone
+    pub start_local: String,
+    pub stop_local: String,
+}
+
+impl From<ScheduledRecording> for ScheduleEntry {
+    fn from(job: ScheduledRecording) -> Self {
+        Self {
+            start_local: local_time(job.start_unix),
+            stop_local: local_time(job.stop_unix),
+            job,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScheduleEvent {
+    id: u64,
+    path: Option<String>,
+    error: Option<String>,
+}
+
+// Jobs are kept in app data so they survive a restart
+pub struct Scheduler {
+    jobs: Mutex<Vec<ScheduledRecording>>,
+    path: PathBuf,
+}
+
+impl Scheduler {
+    pub fn load(path: PathBuf) -> Self {
+        let mut jobs: Vec<ScheduledRecording> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        // Whatever was running died with the previous process; jobs still
+        // inside their window start again on the first tick
+        for job in jobs.iter_mut() {
+            job.active = false;
+            job.session_id = None;
+        }
+        Self {
+            jobs: Mutex::new(jobs),
+            path,
+        }
+    }
+
+    fn save(&self, jobs: &[ScheduledRecording]) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(jobs).map_err(|e| format!("Failed to serialize schedule: {}", e))?;
+        std::fs::write(&self.path, json).map_err(|e| format!("Failed to save schedule: {}", e))
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut Vec<ScheduledRecording>) -> T) -> Result<T, String> {
+        let mut jobs = self.jobs.lock().map_err(|e| e.to_string())?;
+        let result = f(&mut jobs);
+        self.save(&jobs)?;
+        Ok(result)
+    }
+}
+
+pub fn schedule_path(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("schedule.json")
+}
+
+fn local_time(unix: i64) -> String {
+    Local
+        .timestamp_opt(unix, 0)
+        .single()
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
+
+// Record from `start_unix` to `stop_unix` (UTC seconds) with `config`
+#[tauri::command]
+pub async fn schedule_recording(
+    scheduler: State<'_, Scheduler>,
+    start_unix: i64,
+    stop_unix: i64,
+    config: Option<RecordingOptions>,
+) -> Result<ScheduleEntry, String> {
+    if stop_unix <= start_unix {
+        return Err("Scheduled recording must stop after it starts".to_string());
+    }
+    if stop_unix <= Utc::now().timestamp() {
+        return Err("Scheduled recording would already be over".to_string());
+    }
+    scheduler
+        .update(|jobs| {
+            if let Some(other) = jobs.iter().find(|job| start_unix < job.stop_unix && job.start_unix < stop_unix) {
+                return Err(format!(
+                    "Overlaps scheduled recording {} ({} to {})",
+                    other.id,
+                    local_time(other.start_unix),
+                    local_time(other.stop_unix)
+                ));
+            }
+            let job = ScheduledRecording {
+                id: jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1,
+                start_unix,
+                stop_unix,
+                options: config.unwrap_or_default(),
+                active: false,
+                session_id: None,
+            };
+            jobs.push(job.clone());
+            jobs.sort_by_key(|job| job.start_unix);
+            Ok(job.into())
+        })?
+}
+
+#[tauri::command]
+pub async fn list_scheduled(scheduler: State<'_, Scheduler>) -> Result<Vec<ScheduleEntry>, String> {
+    let jobs = scheduler.jobs.lock().map_err(|e| e.to_string())?;
+    Ok(jobs.iter().cloned().map(ScheduleEntry::from).collect())
+}
+
+// Remove a job; if it is already recording, that recording is stopped and
+// its path returned
+#[tauri::command]
+pub async fn cancel_scheduled(
+    app_handle: tauri::AppHandle,
+    scheduler: State<'_, Scheduler>,
+    state: State<'_, RecordingState>,
+    id: u64,
+) -> Result<Option<String>, String> {
+    let removed = scheduler.update(|jobs| {
+        let index = jobs.iter().position(|job| job.id == id)?;
+        Some(jobs.remove(index))
+    })?;
+    match removed {
+        Some(job) if job.active && is_running(&state, job.session_id.as_deref())? => {
+            tauri::async_runtime::spawn_blocking(move || {
+                crate::end_recording(&app_handle, &app_handle.state::<RecordingState>(), StopOptions::default())
+            })
+            .await
+            .map_err(|e| format!("Failed to stop recording: {}", e))?
+            .map(Some)
+        }
+        Some(_) => Ok(None),
+        None => Err(format!("No scheduled recording with id {}", id)),
+    }
+}
+
+enum Action {
+    Start(Box<ScheduledRecording>),
+    Stop(u64, Option<String>),
+    Missed(u64),
+}
+
+// Checks the schedule once a second for the life of the app
+pub fn spawn(app_handle: tauri::AppHandle) {
+    thread::spawn(move || loop {
+        if let Err(e) = tick(&app_handle) {
+            eprintln!("Scheduler error: {}", e);
+        }
+        thread::sleep(Duration::from_millis(TICK_MS));
+    });
+}
+
+fn tick(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let scheduler = app_handle.state::<Scheduler>();
+    let state = app_handle.state::<RecordingState>();
+    let now = Utc::now().timestamp();
+    let due: Vec<Action> = {
+        let jobs = scheduler.jobs.lock().map_err(|e| e.to_string())?;
+        jobs.iter()
+            .filter_map(|job| match (job.active, job.start_unix <= now, job.stop_unix <= now) {
+                (true, _, true) => Some(Action::Stop(job.id, job.session_id.clone())),
+                (false, _, true) => Some(Action::Missed(job.id)),
+                (false, true, false) => Some(Action::Start(Box::new(job.clone()))),
+                _ => None,
+            })
+            .collect()
+    };
+
+    // Starting and stopping can take a while, so the job list isn't held
+    for action in due {
+        match action {
+            Action::Start(job) => {
+                // A file per start, so neither the next job nor a restart
+                // after a crash writes over what was recorded
+                let name = format!("scheduled-{}-{}", job.id, Local::now().format("%Y%m%d-%H%M%S"));
+                match crate::begin_recording(app_handle.clone(), &state, job.options.clone(), None, Some(name)) {
+                    Ok(path) => {
+                        let session_id = state
+                            .session
+                            .lock()
+                            .map_err(|e| e.to_string())?
+                            .as_ref()
+                            .map(|session| session.id.clone());
+                        let still_scheduled = scheduler.update(|jobs| {
+                            jobs.iter_mut()
+                                .find(|other| other.id == job.id)
+                                .map(|job| {
+                                    job.active = true;
+                                    job.session_id = session_id;
+                                })
+                                .is_some()
+                        })?;
+                        // Cancelled while it was starting
+                        if !still_scheduled {
+                            crate::end_recording(app_handle, &state, StopOptions::default())?;
+                            continue;
+                        }
+                        emit(app_handle, "scheduled-recording-started", job.id, Ok(path));
+                    }
+                    Err(e) => {
+                        remove(&scheduler, job.id)?;
+                        emit(app_handle, "scheduled-recording-failed", job.id, Err(e));
+                    }
+                }
+            }
+            Action::Stop(id, session_id) => {
+                remove(&scheduler, id)?;
+                // Stopped by hand already, and maybe replaced by another
+                if !is_running(&state, session_id.as_deref())? {
+                    continue;
+                }
+                let result = crate::end_recording(app_handle, &state, StopOptions::default());
+                emit(app_handle, "scheduled-recording-stopped", id, result);
+            }
+            Action::Missed(id) => {
+                remove(&scheduler, id)?;
+                let reason = "The app wasn't running during the scheduled window".to_string();
+                emit(app_handle, "scheduled-recording-missed", id, Err(reason));
+            }
+        }
+    }
+    Ok(())
+}
+
+// Whether the recording under way is the session a job started
+fn is_running(state: &RecordingState, session_id: Option<&str>) -> Result<bool, String> {
+    if session_id.is_none() || !*state.is_recording.lock().map_err(|e| e.to_string())? {
+        return Ok(false);
+    }
+    let session = state.session.lock().map_err(|e| e.to_string())?;
+    Ok(session.as_ref().map(|session| session.id.as_str()) == session_id)
+}
+
+fn remove(scheduler: &Scheduler, id: u64) -> Result<(), String> {
+    scheduler.update(|jobs| jobs.retain(|job| job.id != id))
+}
+
+fn emit(app_handle: &tauri::AppHandle, event: &str, id: u64, result: Result<String, String>) {
+    let (path, error) = match result {
+        Ok(path) => (Some(path), None),
+        Err(e) => (None, Some(e)),
+    };
+    let _ = app_handle.emit(event, ScheduleEvent { id, path, error });
+}
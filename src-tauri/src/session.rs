@@ -0,0 +1,198 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::recorder::unix_ms;
+use crate::{RecordingOptions, RecordingState};
+
+const SNAPSHOT_VERSION: u32 = 1;
+const STATE_POLL_MS: u64 = 50;
+// A change is sent once the state has held still this long, or after the
+// longer delay at the latest so a busy recording still gets reported
+const STATE_SETTLE_MS: u64 = 100;
+const STATE_MAX_DELAY_MS: u64 = 500;
+
+// One start_recording call; kept after it stops until the next one starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    pub options: RecordingOptions,
+    pub started_unix_ms: u64,
+    // When stop was requested or the recording failed
+    pub stopped_unix_ms: Option<u64>,
+    // Known once the input has opened
+    pub device: Option<String>,
+    // Anything the recording had to work around, or failed on
+    pub warnings: Vec<String>,
+}
+
+impl Session {
+    pub fn new(options: RecordingOptions) -> Result<Self, String> {
+        let mut id = [0u8; 8];
+        getrandom::getrandom(&mut id).map_err(|e| format!("Failed to generate session id: {}", e))?;
+        Ok(Self {
+            id: format!("{:016x}", u64::from_le_bytes(id)),
+            options,
+            started_unix_ms: unix_ms(SystemTime::now()),
+            stopped_unix_ms: None,
+            device: None,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+// Apply `f` to the current session, if there is one
+pub fn update(session: &Mutex<Option<Session>>, f: impl FnOnce(&mut Session)) {
+    if let Ok(mut session) = session.lock() {
+        if let Some(session) = session.as_mut() {
+            f(session);
+        }
+    }
+}
+
+// Everything the UI needs to rebuild itself after a reload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    pub version: u32,
+    pub recording: bool,
+    // Stopped, but the last segment is still being written
+    pub finalizing: bool,
+    pub feedback_enabled: bool,
+    pub window_visible: bool,
+    // An input stream is being held open by keep_stream_alive
+    pub stream_kept_open: bool,
+    pub output_path: Option<String>,
+    pub session: Option<Session>,
+    pub elapsed_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreOutcome {
+    // The snapshot's session is still running and the UI can carry on with it
+    pub reattached: bool,
+    pub state: StateSnapshot,
+}
+
+#[tauri::command]
+pub async fn state_snapshot(state: State<'_, RecordingState>) -> Result<StateSnapshot, String> {
+    snapshot(&state)
+}
+
+// Check a snapshot the UI saved before a crash or reload against the live
+// state. Its feedback setting is re-applied; a recording can only be rejoined,
+// never restarted, so the live state always wins and is returned.
+#[tauri::command]
+pub async fn restore_from_snapshot(
+    state: State<'_, RecordingState>,
+    snapshot: StateSnapshot,
+) -> Result<RestoreOutcome, String> {
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(format!("Unsupported snapshot version {}", snapshot.version));
+    }
+    *state.feedback_enabled.lock().map_err(|e| e.to_string())? = snapshot.feedback_enabled;
+
+    let current = self::snapshot(&state)?;
+    let saved_id = snapshot.session.as_ref().map(|session| session.id.as_str());
+    let live_id = current.session.as_ref().map(|session| session.id.as_str());
+    let reattached = (current.recording || current.finalizing) && saved_id.is_some() && saved_id == live_id;
+    Ok(RestoreOutcome {
+        reattached,
+        state: current,
+    })
+}
+
+pub fn snapshot(state: &RecordingState) -> Result<StateSnapshot, String> {
+    let recording = *state.is_recording.lock().map_err(|e| e.to_string())?;
+    let finalizing = !recording
+        && state
+            .recording_thread
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished());
+    let session = state.session.lock().map_err(|e| e.to_string())?.clone();
+    let elapsed_ms = session.as_ref().map(|session| {
+        let until = session.stopped_unix_ms.unwrap_or_else(|| unix_ms(SystemTime::now()));
+        until.saturating_sub(session.started_unix_ms)
+    });
+    Ok(StateSnapshot {
+        version: SNAPSHOT_VERSION,
+        recording,
+        finalizing,
+        feedback_enabled: *state.feedback_enabled.lock().map_err(|e| e.to_string())?,
+        window_visible: *state.window_visible.lock().map_err(|e| e.to_string())?,
+        stream_kept_open: state
+            .kept_input
+            .lock()
+            .map_err(|e| e.to_string())?
+            .as_ref()
+            .is_some_and(|input| input.is_open()),
+        output_path: state.output_path.lock().map_err(|e| e.to_string())?.clone(),
+        session,
+        elapsed_ms,
+    })
+}
+
+// Send a `recording-state` event with the full snapshot whenever it changes,
+// instead of leaving the UI to piece it together from the other events. One
+// is sent straight away on enabling.
+#[tauri::command]
+pub async fn set_state_events(state: State<'_, RecordingState>, enabled: bool) -> Result<(), String> {
+    *state.state_events.lock().map_err(|e| e.to_string())? = enabled;
+    Ok(())
+}
+
+// Watches the state for the life of the app, sending changes while events
+// are enabled. The elapsed time alone doesn't count as a change.
+pub fn spawn_state_events(app_handle: AppHandle) {
+    thread::spawn(move || {
+        let state = app_handle.state::<RecordingState>();
+        let mut sent: Option<serde_json::Value> = None;
+        // The changed state waiting to settle, when it first changed and when
+        // it last did
+        let mut pending: Option<(serde_json::Value, Instant, Instant)> = None;
+        loop {
+            thread::sleep(Duration::from_millis(STATE_POLL_MS));
+            if !state.state_events.lock().map(|enabled| *enabled).unwrap_or(false) {
+                sent = None;
+                pending = None;
+                continue;
+            }
+            let Ok(current) = snapshot(&state) else {
+                continue;
+            };
+            let Ok(key) = serde_json::to_value(StateSnapshot {
+                elapsed_ms: None,
+                ..current.clone()
+            }) else {
+                continue;
+            };
+            if sent.as_ref() == Some(&key) {
+                pending = None;
+                continue;
+            }
+
+            let now = Instant::now();
+            let (first_changed, last_changed) = match pending.take() {
+                Some((value, first, last)) if value == key => (first, last),
+                Some((_, first, _)) => (first, now),
+                None => (now, now),
+            };
+            // Nothing was sent yet, so there is nothing to debounce against
+            let due = sent.is_none()
+                || last_changed.elapsed() >= Duration::from_millis(STATE_SETTLE_MS)
+                || first_changed.elapsed() >= Duration::from_millis(STATE_MAX_DELAY_MS);
+            if due {
+                let _ = app_handle.emit("recording-state", current);
+                sent = Some(key);
+            } else {
+                pending = Some((key, first_changed, last_changed));
+            }
+        }
+    });
+}
@@ -0,0 +1,109 @@
+use std::path::Path;
+use serde::Serialize;
+
+use crate::decode;
+use crate::levels;
+
+// Overall correlation below this cancels noticeably when summed to mono
+const PHASE_ISSUE_BELOW: f64 = 0.0;
+const MIN_WINDOW_MS: u64 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrelationWindow {
+    pub at_secs: f64,
+    // None for silent windows
+    pub correlation: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StereoCorrelation {
+    // Pearson correlation of left and right, -1 (inverted) to +1 (mono)
+    pub correlation: f32,
+    pub phase_issue: bool,
+    // Level of the mono sum relative to the average channel: 0 dB when the
+    // channels are identical, about -3 dB when unrelated, far lower when inverted
+    pub mono_sum_db: f32,
+    pub windows: Vec<CorrelationWindow>,
+}
+
+// Running sums for the correlation of two channels
+#[derive(Debug, Clone, Copy, Default)]
+struct Sums {
+    left: f64,
+    right: f64,
+    left_sq: f64,
+    right_sq: f64,
+    product: f64,
+    count: f64,
+}
+
+impl Sums {
+    fn push(&mut self, left: f64, right: f64) {
+        self.left += left;
+        self.right += right;
+        self.left_sq += left * left;
+        self.right_sq += right * right;
+        self.product += left * right;
+        self.count += 1.0;
+    }
+
+    fn correlation(&self) -> Option<f64> {
+        let n = self.count;
+        let covariance = n * self.product - self.left * self.right;
+        let spread = (n * self.left_sq - self.left * self.left) * (n * self.right_sq - self.right * self.right);
+        (spread > f64::EPSILON).then(|| (covariance / spread.sqrt()).clamp(-1.0, 1.0))
+    }
+}
+
+// Correlation between the channels of a stereo WAV, overall and per window
+#[tauri::command]
+pub async fn stereo_correlation(path: String, window_ms: Option<u64>) -> Result<StereoCorrelation, String> {
+    let mut source = decode::open(Path::new(&path))?;
+    let spec = source.info();
+    if spec.channels != 2 {
+        return Err(format!("Correlation needs a stereo recording, this one has {} channels", spec.channels));
+    }
+    let window_ms = window_ms.unwrap_or(500).max(MIN_WINDOW_MS);
+    let window_frames = (spec.sample_rate as u64 * window_ms / 1000).max(1) as usize;
+
+    let mut total = Sums::default();
+    let mut window = Sums::default();
+    let mut windows = Vec::new();
+    let mut frame_index = 0usize;
+    let mut mono_energy = 0.0f64;
+    let mut buffer = Vec::new();
+    while source.read_chunk(&mut buffer)? > 0 {
+        for frame in buffer.chunks_exact(2) {
+            let (left, right) = (frame[0] as f64, frame[1] as f64);
+            total.push(left, right);
+            window.push(left, right);
+            mono_energy += ((left + right) / 2.0).powi(2);
+            frame_index += 1;
+            if window.count as usize == window_frames {
+                windows.push(CorrelationWindow {
+                    at_secs: (frame_index - window_frames) as f64 / spec.sample_rate as f64,
+                    correlation: window.correlation().map(|c| c as f32),
+                });
+                window = Sums::default();
+            }
+        }
+    }
+
+    let correlation = total
+        .correlation()
+        .ok_or_else(|| "Recording is silent or one channel is constant".to_string())?;
+    let channel_energy = (total.left_sq + total.right_sq) / 2.0;
+    let mono_sum_db = if mono_energy > 0.0 {
+        (10.0 * (mono_energy / channel_energy).log10()) as f32
+    } else {
+        levels::MIN_DBFS
+    };
+    Ok(StereoCorrelation {
+        correlation: correlation as f32,
+        phase_issue: correlation < PHASE_ISSUE_BELOW,
+        mono_sum_db,
+        windows,
+    })
+}
@@ -0,0 +1,306 @@
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State};
+
+use crate::capture;
+use crate::decode;
+use crate::playback;
+use crate::wav::{self, WavSink};
+use crate::RecordingState;
+
+const FADE_MS: f64 = 10.0;
+const MAX_SWEEP_SECS: f64 = 30.0;
+const MAX_SILENCE_SECS: f64 = 10.0;
+// Floor of the spectral division, relative to the sweep's peak power, so bins
+// outside the swept band don't blow up. An exponential sweep's spectrum falls
+// about 30 dB across the audio band, so this has to sit well below that.
+const REGULARIZATION: f64 = 1e-6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SweepParams {
+    pub start_hz: f64,
+    pub end_hz: f64,
+    pub duration_secs: f64,
+    pub amplitude: f32,
+    // Silence before the sweep, and after it for the room's decay
+    pub lead_secs: f64,
+    pub tail_secs: f64,
+    // Filled in from the output device when the sweep is recorded
+    pub sample_rate: u32,
+}
+
+impl Default for SweepParams {
+    fn default() -> Self {
+        Self {
+            start_hz: 20.0,
+            end_hz: 20_000.0,
+            duration_secs: 5.0,
+            amplitude: 0.5,
+            lead_secs: 0.25,
+            tail_secs: 1.5,
+            sample_rate: 0,
+        }
+    }
+}
+
+impl SweepParams {
+    fn validate(&self) -> Result<(), String> {
+        let nyquist = self.sample_rate as f64 / 2.0;
+        if !(self.start_hz > 0.0 && self.start_hz < self.end_hz) {
+            return Err("Sweep start frequency must be positive and below the end frequency".to_string());
+        }
+        if self.end_hz > nyquist {
+            return Err(format!("Sweep end frequency must not exceed {} Hz at this sample rate", nyquist));
+        }
+        if !(self.duration_secs > 0.0 && self.duration_secs <= MAX_SWEEP_SECS) {
+            return Err(format!("Sweep duration must be between 0 and {} seconds", MAX_SWEEP_SECS));
+        }
+        if !(self.amplitude > 0.0 && self.amplitude <= 1.0) {
+            return Err("Sweep amplitude must be between 0 and 1".to_string());
+        }
+        for silence in [self.lead_secs, self.tail_secs] {
+            if !(0.0..=MAX_SILENCE_SECS).contains(&silence) {
+                return Err(format!("Lead and tail silence must be between 0 and {} seconds", MAX_SILENCE_SECS));
+            }
+        }
+        Ok(())
+    }
+
+    // Exponential sine sweep (Farina), faded in and out to avoid clicks
+    pub fn render(&self) -> Vec<f32> {
+        let rate = self.sample_rate as f64;
+        let len = (self.duration_secs * rate) as usize;
+        let ratio = (self.end_hz / self.start_hz).ln();
+        let scale = 2.0 * PI * self.start_hz * self.duration_secs / ratio;
+        let fade = (FADE_MS / 1000.0 * rate).max(1.0);
+        (0..len)
+            .map(|n| {
+                let t = n as f64 / rate;
+                let phase = scale * ((t * ratio / self.duration_secs).exp() - 1.0);
+                let envelope = (n as f64 / fade).min((len - n) as f64 / fade).min(1.0);
+                (self.amplitude as f64 * envelope * phase.sin()) as f32
+            })
+            .collect()
+    }
+}
+
+// Where the sweep parameters for a measurement recording are kept
+fn sidecar_path(recording: &Path) -> PathBuf {
+    recording.with_extension("sweep.json")
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SweepRecording {
+    pub path: String,
+    pub params: SweepParams,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImpulseResponse {
+    pub path: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub frames: usize,
+    // Round-trip delay from output to input, from the position of the peak
+    pub latency_ms: f64,
+}
+
+// Play a sweep on the output while recording the input, for deriving an
+// impulse response with compute_impulse_response
+#[tauri::command]
+pub async fn record_sweep(
+    app_handle: tauri::AppHandle,
+    state: State<'_, RecordingState>,
+    params: Option<SweepParams>,
+    input_device: Option<String>,
+    output_device: Option<String>,
+) -> Result<SweepRecording, String> {
+    if *state.is_recording.lock().map_err(|e| e.to_string())? {
+        return Err("Cannot measure while recording".to_string());
+    }
+
+    let output = playback::find_output_device(output_device.as_deref())?;
+    let output_supported = output
+        .default_output_config()
+        .map_err(|e| format!("Failed to get default output config: {}", e))?;
+    let output_format = output_supported.sample_format();
+    let output_config: cpal::StreamConfig = output_supported.into();
+    let sample_rate = output_config.sample_rate.0;
+
+    let mut params = params.unwrap_or_default();
+    params.sample_rate = sample_rate;
+    params.validate()?;
+
+    // Deconvolution needs input and output on the same clock rate
+    let input = capture::find_input_device(input_device.as_deref())?;
+    let input_supported = capture::select_input_config(&input, Some(sample_rate), false)?;
+    let input_format = input_supported.sample_format();
+    let input_config: cpal::StreamConfig = input_supported.into();
+
+    let app_data_dir = app_handle.path().app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    std::fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let path = app_data_dir.join("sweep-measurement.wav");
+
+    struct Capture {
+        sink: Option<WavSink>,
+        error: Option<String>,
+    }
+    let capture = Arc::new(Mutex::new(Capture {
+        sink: Some(WavSink::create(&path, wav::float_spec(input_config.channels, sample_rate))?),
+        error: None,
+    }));
+    let capture_ref = capture.clone();
+    let input_stream = capture::build_f32_input_stream(&input, &input_config, input_format, move |data| {
+        if let Ok(mut capture) = capture_ref.lock() {
+            if let Some(Err(e)) = capture.sink.as_mut().map(|sink| sink.write(data)) {
+                capture.error.get_or_insert(e);
+            }
+        }
+    })?;
+
+    let lead = (params.lead_secs * sample_rate as f64) as usize;
+    let mut signal = vec![0.0; lead];
+    signal.extend(params.render());
+    let output_channels = output_config.channels as usize;
+    let mut position = 0;
+    let output_stream = playback::build_f32_output_stream(&output, &output_config, output_format, move |data| {
+        for frame in data.chunks_mut(output_channels) {
+            frame.fill(signal.get(position).copied().unwrap_or(0.0));
+            position += 1;
+        }
+    })?;
+
+    input_stream.play().map_err(|e| format!("Failed to start input stream: {}", e))?;
+    output_stream.play().map_err(|e| format!("Failed to start output stream: {}", e))?;
+    thread::sleep(Duration::from_secs_f64(params.lead_secs + params.duration_secs + params.tail_secs));
+    drop(output_stream);
+    drop(input_stream);
+
+    let mut capture = capture.lock().map_err(|e| e.to_string())?;
+    if let Some(e) = capture.error.take() {
+        return Err(e);
+    }
+    if let Some(sink) = capture.sink.take() {
+        sink.finalize()?;
+    }
+    let json = serde_json::to_string_pretty(&params).map_err(|e| format!("Failed to serialize sweep parameters: {}", e))?;
+    std::fs::write(sidecar_path(&path), json).map_err(|e| format!("Failed to write sweep parameters: {}", e))?;
+
+    Ok(SweepRecording {
+        path: path.to_string_lossy().to_string(),
+        params,
+    })
+}
+
+// Deconvolve a sweep recording into an impulse response by regularized
+// spectral division, writing `length_secs` (default: the sweep's tail) from
+// the moment the sweep started playing
+#[tauri::command]
+pub async fn compute_impulse_response(
+    recording: String,
+    sweep_params: Option<SweepParams>,
+    output: String,
+    length_secs: Option<f64>,
+) -> Result<ImpulseResponse, String> {
+    let recording = Path::new(&recording);
+    let params = match sweep_params {
+        Some(params) => params,
+        None => {
+            let json = std::fs::read_to_string(sidecar_path(recording))
+                .map_err(|e| format!("No sweep parameters given or stored with the recording: {}", e))?;
+            serde_json::from_str(&json).map_err(|e| format!("Failed to parse sweep parameters: {}", e))?
+        }
+    };
+
+    let mut source = decode::open(recording)?;
+    let spec = source.info();
+    if params.sample_rate != spec.sample_rate {
+        return Err(format!(
+            "Recording is {} Hz but the sweep was generated at {} Hz",
+            spec.sample_rate, params.sample_rate
+        ));
+    }
+    params.validate()?;
+    let channels = spec.channels as usize;
+    let mut recorded: Vec<Vec<f64>> = vec![Vec::new(); channels];
+    let mut buffer = Vec::new();
+    while source.read_chunk(&mut buffer)? > 0 {
+        for frame in buffer.chunks_exact(channels) {
+            for (channel, &sample) in recorded.iter_mut().zip(frame) {
+                channel.push(sample as f64);
+            }
+        }
+    }
+
+    let sweep = params.render();
+    let recorded_len = recorded.first().map(Vec::len).unwrap_or(0);
+    let lead = (params.lead_secs * spec.sample_rate as f64) as usize;
+    if recorded_len < lead + sweep.len() {
+        return Err("Recording is shorter than the lead silence and sweep".to_string());
+    }
+    let size = (recorded_len + sweep.len()).next_power_of_two();
+    let mut planner = FftPlanner::<f64>::new();
+    let forward = planner.plan_fft_forward(size);
+    let inverse = planner.plan_fft_inverse(size);
+
+    let mut reference: Vec<Complex<f64>> = sweep.iter().map(|&s| Complex::new(s as f64, 0.0)).collect();
+    reference.resize(size, Complex::new(0.0, 0.0));
+    forward.process(&mut reference);
+    let peak_power = reference.iter().map(|bin| bin.norm_sqr()).fold(0.0, f64::max);
+    let floor = peak_power * REGULARIZATION;
+
+    let length = (length_secs.unwrap_or(params.tail_secs).max(0.0) * spec.sample_rate as f64) as usize;
+    let length = length.min(size.saturating_sub(lead)).max(1);
+    let mut responses = Vec::with_capacity(channels);
+    let mut peak: (f64, usize) = (0.0, 0);
+    for channel in &recorded {
+        let mut spectrum: Vec<Complex<f64>> = channel.iter().map(|&s| Complex::new(s, 0.0)).collect();
+        spectrum.resize(size, Complex::new(0.0, 0.0));
+        forward.process(&mut spectrum);
+        for (bin, reference) in spectrum.iter_mut().zip(&reference) {
+            *bin = *bin * reference.conj() / (reference.norm_sqr() + floor);
+        }
+        inverse.process(&mut spectrum);
+        let response: Vec<f64> = spectrum[lead..lead + length].iter().map(|bin| bin.re / size as f64).collect();
+        if let Some((offset, &value)) = response
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+        {
+            if value.abs() > peak.0 {
+                peak = (value.abs(), offset);
+            }
+        }
+        responses.push(response);
+    }
+
+    let mut sink = WavSink::create(Path::new(&output), wav::float_spec(spec.channels, spec.sample_rate))?;
+    let mut frame = vec![0.0f32; channels];
+    for index in 0..length {
+        for (sample, response) in frame.iter_mut().zip(&responses) {
+            *sample = response[index] as f32;
+        }
+        sink.write(&frame)?;
+    }
+    sink.finalize()?;
+
+    Ok(ImpulseResponse {
+        path: output,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        frames: length,
+        latency_ms: peak.1 as f64 * 1000.0 / spec.sample_rate as f64,
+    })
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+use lofty::config::WriteOptions;
+use lofty::file::{FileType, TaggedFileExt};
+use lofty::tag::{ItemKey, Tag, TagExt, TagType};
+use serde::{Deserialize, Serialize};
+
+// Format-independent view of a recording's tags. In set_tags a missing field
+// is left alone and an empty string removes it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub comment: Option<String>,
+    pub date: Option<String>,
+    pub genre: Option<String>,
+    pub track_number: Option<String>,
+}
+
+impl AudioTags {
+    fn fields(&self) -> [(ItemKey, &Option<String>); 7] {
+        [
+            (ItemKey::TrackTitle, &self.title),
+            (ItemKey::TrackArtist, &self.artist),
+            (ItemKey::AlbumTitle, &self.album),
+            (ItemKey::Comment, &self.comment),
+            (ItemKey::RecordingDate, &self.date),
+            (ItemKey::Genre, &self.genre),
+            (ItemKey::TrackNumber, &self.track_number),
+        ]
+    }
+
+    pub fn has_any(&self) -> bool {
+        self.fields().iter().any(|(_, value)| value.is_some())
+    }
+
+    fn fields_mut(&mut self) -> [(ItemKey, &mut Option<String>); 7] {
+        [
+            (ItemKey::TrackTitle, &mut self.title),
+            (ItemKey::TrackArtist, &mut self.artist),
+            (ItemKey::AlbumTitle, &mut self.album),
+            (ItemKey::Comment, &mut self.comment),
+            (ItemKey::RecordingDate, &mut self.date),
+            (ItemKey::Genre, &mut self.genre),
+            (ItemKey::TrackNumber, &mut self.track_number),
+        ]
+    }
+}
+
+// Tag formats written for a file: the format's native one (ID3v2 for MP3,
+// AIFF and WAV, Vorbis comments for FLAC and Ogg), plus RIFF INFO for WAV so
+// tools that only read INFO chunks see the tags too. The broadcast `bext`
+// chunk isn't supported by lofty and is left untouched.
+fn tag_types(file_type: FileType) -> Vec<TagType> {
+    let mut types = vec![file_type.primary_tag_type()];
+    if file_type == FileType::Wav {
+        types.push(TagType::RiffInfo);
+    }
+    types
+}
+
+#[tauri::command]
+pub async fn set_tags(path: String, tags: AudioTags) -> Result<AudioTags, String> {
+    write_tags(Path::new(&path), &tags)?;
+    read_tags(Path::new(&path))
+}
+
+pub fn write_tags(path: &Path, tags: &AudioTags) -> Result<(), String> {
+    let file = lofty::read_from_path(path).map_err(|e| format!("Failed to read tags: {}", e))?;
+    for tag_type in tag_types(file.file_type()) {
+        let mut tag = file.tag(tag_type).cloned().unwrap_or_else(|| Tag::new(tag_type));
+        for (key, value) in tags.fields() {
+            match value.as_deref() {
+                Some("") => tag.remove_key(&key),
+                Some(value) => {
+                    tag.insert_text(key, value.to_string());
+                }
+                None => {}
+            }
+        }
+        tag.save_to_path(path, WriteOptions::default())
+            .map_err(|e| format!("Failed to write tags: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_tags(path: String) -> Result<AudioTags, String> {
+    read_tags(Path::new(&path))
+}
+
+// Each field comes from the first tag that has it, native format first
+pub fn read_tags(path: &Path) -> Result<AudioTags, String> {
+    let file = lofty::read_from_path(path).map_err(|e| format!("Failed to read tags: {}", e))?;
+    let mut ordered: Vec<&Tag> = file.primary_tag().into_iter().collect();
+    ordered.extend(file.tags().iter().filter(|tag| tag.tag_type() != file.primary_tag_type()));
+
+    let mut tags = AudioTags::default();
+    for (key, value) in tags.fields_mut() {
+        *value = ordered.iter().find_map(|tag| tag.get_string(&key)).map(str::to_string);
+    }
+    Ok(tags)
+}
@@ -0,0 +1,192 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::decode;
+use crate::levels::{self, BlockRms};
+
+const VAD_BLOCK_MS: u64 = 30;
+// Speech is whatever sits this far above the noise floor, though never
+// needing more than the cap when the recording has little quiet in it
+const VAD_MARGIN_DB: f32 = 12.0;
+const VAD_MAX_THRESHOLD_DBFS: f32 = -35.0;
+// Pauses shorter than this stay inside one cue; shorter bursts are dropped
+const VAD_HANGOVER_MS: u64 = 300;
+const VAD_MIN_SPEECH_MS: u64 = 200;
+// How long a marker without an end stays on screen
+const MARKER_CUE_SECS: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineFormat {
+    Srt,
+    Vtt,
+}
+
+impl TimelineFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            TimelineFormat::Srt => "srt",
+            TimelineFormat::Vtt => "vtt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    pub start_secs: f64,
+    // A point marker when unset
+    pub end_secs: Option<f64>,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimelineOptions {
+    // Bookmarks from the UI, in order
+    pub markers: Vec<TimelineEntry>,
+    // Add a cue for every stretch of detected speech
+    pub voice: bool,
+    pub voice_label: String,
+    // Next to the recording with the format's extension when unset
+    pub output: Option<String>,
+}
+
+impl Default for TimelineOptions {
+    fn default() -> Self {
+        Self {
+            markers: Vec::new(),
+            voice: true,
+            voice_label: "Speech".to_string(),
+            output: None,
+        }
+    }
+}
+
+// Write the recording's markers and detected speech as SRT or WebVTT cues,
+// for dropping onto a video editor's timeline. Returns the file written.
+#[tauri::command]
+pub async fn export_timeline(
+    path: String,
+    format: TimelineFormat,
+    options: Option<TimelineOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let input = Path::new(&path);
+    let (duration_secs, voice) = scan(input, options.voice)?;
+
+    let mut previous_start = 0.0;
+    for marker in &options.markers {
+        if !(marker.start_secs >= previous_start && marker.start_secs <= duration_secs) {
+            return Err(format!(
+                "Marker \"{}\" at {:.3} s is out of order or past the end ({:.3} s)",
+                marker.label, marker.start_secs, duration_secs
+            ));
+        }
+        if marker.end_secs.is_some_and(|end| !(end >= marker.start_secs && end <= duration_secs)) {
+            return Err(format!("Marker \"{}\" must end after it starts and within the recording", marker.label));
+        }
+        previous_start = marker.start_secs;
+    }
+
+    let mut cues: Vec<(f64, f64, &str)> = options
+        .markers
+        .iter()
+        .map(|marker| {
+            let end = marker.end_secs.unwrap_or((marker.start_secs + MARKER_CUE_SECS).min(duration_secs));
+            (marker.start_secs, end, marker.label.as_str())
+        })
+        .chain(voice.iter().map(|&(start, end)| (start, end, options.voice_label.as_str())))
+        .collect();
+    if cues.is_empty() {
+        return Err("Timeline has nothing to export".to_string());
+    }
+    cues.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut text = String::new();
+    if format == TimelineFormat::Vtt {
+        text.push_str("WEBVTT\n\n");
+    }
+    for (index, (start, end, label)) in cues.iter().enumerate() {
+        if format == TimelineFormat::Srt {
+            let _ = writeln!(text, "{}", index + 1);
+        }
+        let _ = writeln!(text, "{} --> {}", timestamp(*start, format), timestamp(*end, format));
+        // A blank line would end the cue early
+        for line in label.lines().filter(|line| !line.trim().is_empty()) {
+            let _ = writeln!(text, "{}", line);
+        }
+        text.push('\n');
+    }
+
+    let output = match options.output {
+        Some(output) => output,
+        None => input.with_extension(format.extension()).to_string_lossy().to_string(),
+    };
+    std::fs::write(&output, text).map_err(|e| format!("Failed to write timeline: {}", e))?;
+    Ok(output)
+}
+
+// `HH:MM:SS,mmm` for SRT, `HH:MM:SS.mmm` for WebVTT
+pub fn timestamp(secs: f64, format: TimelineFormat) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    let separator = if format == TimelineFormat::Srt { ',' } else { '.' };
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+// The recording's length and, if asked for, its stretches of speech
+pub fn scan(path: &Path, voice: bool) -> Result<(f64, Vec<(f64, f64)>), String> {
+    let mut source = decode::open(path)?;
+    let info = source.info();
+    let block_frames = (info.sample_rate as u64 * VAD_BLOCK_MS / 1000) as usize;
+    let mut blocks = BlockRms::new(block_frames, info.channels as usize);
+    let mut frames = 0u64;
+    let mut buffer = Vec::new();
+    loop {
+        let read = source.read_chunk(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        frames += read as u64;
+        if voice {
+            blocks.push_interleaved(&buffer);
+        }
+    }
+    let duration_secs = frames as f64 / info.sample_rate as f64;
+    let speech = if voice {
+        speech_segments(blocks.blocks_dbfs(), VAD_BLOCK_MS as f64 / 1000.0)
+    } else {
+        Vec::new()
+    };
+    Ok((duration_secs, speech))
+}
+
+// Start and end in seconds of each run of blocks loud enough to be speech,
+// bridging short pauses and dropping short bursts
+pub fn speech_segments(blocks_dbfs: &[f32], block_secs: f64) -> Vec<(f64, f64)> {
+    let floor = levels::percentile_dbfs(blocks_dbfs, 0.1);
+    let threshold = (floor + VAD_MARGIN_DB).min(VAD_MAX_THRESHOLD_DBFS);
+    let hangover = VAD_HANGOVER_MS as f64 / 1000.0;
+    let mut segments: Vec<(f64, f64)> = Vec::new();
+    for (index, level) in blocks_dbfs.iter().enumerate() {
+        if *level < threshold {
+            continue;
+        }
+        let start = index as f64 * block_secs;
+        let end = start + block_secs;
+        match segments.last_mut() {
+            Some(last) if start - last.1 <= hangover => last.1 = end,
+            _ => segments.push((start, end)),
+        }
+    }
+    segments.retain(|(start, end)| end - start >= VAD_MIN_SPEECH_MS as f64 / 1000.0);
+    segments
+}
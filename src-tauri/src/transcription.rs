@@ -0,0 +1,75 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::manifest;
+
+// Pricing and speed of a transcription service, as set by the user
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionService {
+    pub price_per_minute: f64,
+    // Shown with the cost; the estimate doesn't convert anything
+    pub currency: Option<String>,
+    // Audio is billed in whole blocks of this many seconds, e.g. 15; billed
+    // exactly when unset
+    pub billing_increment_secs: Option<f64>,
+    // Anything shorter is billed as this long
+    pub minimum_billed_secs: Option<f64>,
+    // Seconds of audio transcribed per second of waiting, e.g. 10 for ten
+    // times real time; 1 when unset
+    pub speed_factor: Option<f64>,
+    // Upload and queueing time added to every job
+    pub overhead_secs: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptionEstimate {
+    pub duration_secs: f64,
+    pub billed_secs: f64,
+    pub cost: f64,
+    pub currency: Option<String>,
+    pub processing_secs: f64,
+}
+
+// What transcribing a recording would cost and how long it would take. Give
+// `path` for an existing recording or `duration_secs` to plan one.
+#[tauri::command]
+pub async fn estimate_transcription(
+    path: Option<String>,
+    duration_secs: Option<f64>,
+    service: TranscriptionService,
+) -> Result<TranscriptionEstimate, String> {
+    let duration_secs = match (path, duration_secs) {
+        (Some(path), None) => manifest::duration_secs(Path::new(&path))?,
+        (None, Some(duration_secs)) if duration_secs.is_finite() && duration_secs >= 0.0 => duration_secs,
+        (None, Some(_)) => return Err("Duration can't be negative".to_string()),
+        _ => return Err("Give either a recording or a duration".to_string()),
+    };
+    let non_negative = |value: Option<f64>| value.is_none_or(|value| value.is_finite() && value >= 0.0);
+    if !(non_negative(Some(service.price_per_minute))
+        && non_negative(service.minimum_billed_secs)
+        && non_negative(service.overhead_secs))
+    {
+        return Err("Price, minimum and overhead can't be negative".to_string());
+    }
+    if service.billing_increment_secs.is_some_and(|secs| !(secs > 0.0 && secs.is_finite())) {
+        return Err("Billing increment must be above 0 seconds".to_string());
+    }
+    let speed_factor = service.speed_factor.unwrap_or(1.0);
+    if !(speed_factor > 0.0 && speed_factor.is_finite()) {
+        return Err("Speed factor must be above 0".to_string());
+    }
+
+    let mut billed_secs = duration_secs.max(service.minimum_billed_secs.unwrap_or(0.0));
+    if let Some(increment) = service.billing_increment_secs {
+        billed_secs = (billed_secs / increment).ceil() * increment;
+    }
+    Ok(TranscriptionEstimate {
+        duration_secs,
+        billed_secs,
+        cost: billed_secs / 60.0 * service.price_per_minute,
+        currency: service.currency,
+        processing_secs: service.overhead_secs.unwrap_or(0.0) + duration_secs / speed_factor,
+    })
+}
@@ -0,0 +1,168 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+
+// Reads a WAV file in chunks of interleaved f32 frames, whatever its sample
+// format, so long recordings never have to be loaded into memory
+pub struct WavStream {
+    reader: WavReader<BufReader<File>>,
+    spec: WavSpec,
+}
+
+impl WavStream {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let reader = WavReader::open(path).map_err(|e| format!("Failed to open WAV file: {}", e))?;
+        let spec = reader.spec();
+        if spec.channels == 0 {
+            return Err("WAV file has no channels".to_string());
+        }
+        Ok(Self { reader, spec })
+    }
+
+    pub fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    // Move to `frame`, counted from the start of the file
+    pub fn seek(&mut self, frame: u32) -> Result<(), String> {
+        self.reader.seek(frame).map_err(|e| format!("Failed to seek in WAV file: {}", e))
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.reader.duration()
+    }
+
+    // Fill `buffer` with up to `max_frames` frames; returns the number of
+    // frames read, 0 at end of file
+    pub fn read_chunk(&mut self, max_frames: usize, buffer: &mut Vec<f32>) -> Result<usize, String> {
+        buffer.clear();
+        let max_samples = max_frames * self.spec.channels as usize;
+        match self.spec.sample_format {
+            SampleFormat::Float => {
+                for sample in self.reader.samples::<f32>().take(max_samples) {
+                    buffer.push(sample.map_err(|e| format!("Failed to read WAV sample: {}", e))?);
+                }
+            }
+            SampleFormat::Int => {
+                let scale = int_scale(self.spec.bits_per_sample);
+                for sample in self.reader.samples::<i32>().take(max_samples) {
+                    let sample = sample.map_err(|e| format!("Failed to read WAV sample: {}", e))?;
+                    buffer.push(sample as f32 / scale);
+                }
+            }
+        }
+        Ok(buffer.len() / self.spec.channels as usize)
+    }
+}
+
+// Writes interleaved f32 frames into a WAV file of the given spec
+pub struct WavSink {
+    writer: WavWriter<BufWriter<File>>,
+    spec: WavSpec,
+}
+
+impl WavSink {
+    pub fn create(path: &Path, spec: WavSpec) -> Result<Self, String> {
+        let writer = WavWriter::create(path, spec).map_err(|e| format!("Failed to create WAV file: {}", e))?;
+        Ok(Self { writer, spec })
+    }
+
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), String> {
+        let result = match self.spec.sample_format {
+            SampleFormat::Float => samples.iter().try_for_each(|&sample| self.writer.write_sample(sample)),
+            SampleFormat::Int => {
+                let scale = int_scale(self.spec.bits_per_sample);
+                let max = scale - 1.0;
+                samples.iter().try_for_each(|&sample| {
+                    let value = (sample * scale).clamp(-scale, max) as i32;
+                    self.writer.write_sample(value)
+                })
+            }
+        };
+        result.map_err(|e| format!("Failed to write WAV sample: {}", e))
+    }
+
+    pub fn finalize(self) -> Result<(), String> {
+        self.writer.finalize().map_err(|e| format!("Failed to finalize WAV file: {}", e))
+    }
+}
+
+// Full-scale magnitude of an integer sample with the given bit depth
+fn int_scale(bits_per_sample: u16) -> f32 {
+    (1u32 << (bits_per_sample - 1)) as f32
+}
+
+// The 16-bit integer spec the recorder and encoders work with
+pub fn pcm16_spec(channels: u16, sample_rate: u32) -> WavSpec {
+    WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    }
+}
+
+// 32-bit float, for measurements that shouldn't be quantized
+pub fn float_spec(channels: u16, sample_rate: u32) -> WavSpec {
+    WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    }
+}
+
+// Add a chunk after everything else in a finished WAV file, growing the RIFF
+// size to cover it. Readers skip chunk ids they don't know.
+pub fn append_chunk(path: &Path, id: [u8; 4], data: &[u8]) -> Result<(), String> {
+    let io_error = |e: std::io::Error| format!("Failed to add {} chunk: {}", String::from_utf8_lossy(&id), e);
+    let mut file = OpenOptions::new().read(true).write(true).open(path).map_err(io_error)?;
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header).map_err(io_error)?;
+    if &header[..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+
+    let end = file.seek(SeekFrom::End(0)).map_err(io_error)?;
+    // Chunks start on even offsets and are padded to an even length
+    let mut chunk = Vec::with_capacity(data.len() + 10);
+    if end % 2 == 1 {
+        chunk.push(0);
+    }
+    chunk.extend_from_slice(&id);
+    chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        chunk.push(0);
+    }
+    let riff_size = u32::try_from(end + chunk.len() as u64 - 8)
+        .map_err(|_| "WAV file is too large for another chunk".to_string())?;
+    file.write_all(&chunk).map_err(io_error)?;
+    file.seek(SeekFrom::Start(4)).map_err(io_error)?;
+    file.write_all(&riff_size.to_le_bytes()).map_err(io_error)
+}
+
+// Contents of the first chunk with `id` in a WAV file, if it has one
+pub fn find_chunk(path: &Path, id: [u8; 4]) -> Result<Option<Vec<u8>>, String> {
+    let io_error = |e: std::io::Error| format!("Failed to read WAV file: {}", e);
+    let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header).map_err(io_error)?;
+    if &header[..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+    loop {
+        let mut chunk_header = [0u8; 8];
+        if reader.read_exact(&mut chunk_header).is_err() {
+            return Ok(None);
+        }
+        let size = u32::from_le_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]);
+        if chunk_header[..4] == id {
+            let mut data = vec![0u8; size as usize];
+            reader.read_exact(&mut data).map_err(io_error)?;
+            return Ok(Some(data));
+        }
+        reader.seek_relative(size as i64 + (size % 2) as i64).map_err(io_error)?;
+    }
+}
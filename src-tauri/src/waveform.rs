@@ -0,0 +1,68 @@
+use std::path::Path;
+use serde::Serialize;
+
+use crate::decode;
+
+const MAX_BUCKETS: usize = 100_000;
+
+// Min/max peaks (all channels combined) of `buckets` equal slices of a WAV file
+pub fn overview(path: &Path, buckets: usize) -> Result<Vec<(f32, f32)>, String> {
+    if buckets == 0 || buckets > MAX_BUCKETS {
+        return Err(format!("Bucket count must be between 1 and {}", MAX_BUCKETS));
+    }
+    let mut source = decode::open(path)?;
+    let channels = source.info().channels as usize;
+    let total = source.info().frames.unwrap_or(0).max(1);
+    let mut peaks = vec![(0.0f32, 0.0f32); buckets];
+    let mut buffer = Vec::new();
+    let mut frame_index = 0u64;
+    while source.read_chunk(&mut buffer)? > 0 {
+        for frame in buffer.chunks_exact(channels) {
+            let bucket = ((frame_index * buckets as u64 / total) as usize).min(buckets - 1);
+            let (min, max) = &mut peaks[bucket];
+            for &sample in frame {
+                *min = min.min(sample);
+                *max = max.max(sample);
+            }
+            frame_index += 1;
+        }
+    }
+    Ok(peaks)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveformSvg {
+    // Closed outline: max peaks left to right, then min peaks back
+    pub d: String,
+    pub svg: String,
+}
+
+#[tauri::command]
+pub async fn waveform_svg_path(path: String, width: f64, height: f64, buckets: usize) -> Result<WaveformSvg, String> {
+    if !(width > 0.0 && height > 0.0) {
+        return Err("Width and height must be positive".to_string());
+    }
+    let peaks = overview(Path::new(&path), buckets)?;
+    let step = width / peaks.len() as f64;
+    let middle = height / 2.0;
+    let y = |value: f32| middle - value.clamp(-1.0, 1.0) as f64 * middle;
+
+    let mut d = String::with_capacity(peaks.len() * 24);
+    for (index, &(_, max)) in peaks.iter().enumerate() {
+        let command = if index == 0 { 'M' } else { 'L' };
+        d.push_str(&format!("{}{:.2},{:.2}", command, (index as f64 + 0.5) * step, y(max)));
+    }
+    for (index, &(min, _)) in peaks.iter().enumerate().rev() {
+        d.push_str(&format!("L{:.2},{:.2}", (index as f64 + 0.5) * step, y(min)));
+    }
+    d.push('Z');
+
+    let svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"{w}\" height=\"{h}\"><path d=\"{d}\" fill=\"currentColor\"/></svg>",
+        w = width,
+        h = height,
+        d = d
+    );
+    Ok(WaveformSvg { d, svg })
+}